@@ -1,7 +1,6 @@
 use tokio::time;
 use anyhow::Result;
-use base64::Engine;
-use cdp_html_shot::Browser;
+use cdp_html_shot::{Browser, CaptureOptions};
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -26,12 +25,9 @@ async fn main() -> Result<()> {
     tab.set_content(HTML).await?;
 
     let element = tab.find_element("html").await?;
-    let base64 = element.screenshot().await?;
+    element.save_to_file("test0.jpeg", CaptureOptions::new()).await?;
     tab.close().await?;
 
-    let img_data = base64::prelude::BASE64_STANDARD.decode(base64)?;
-    std::fs::write("test0.jpeg", img_data)?;
-
     time::sleep(time::Duration::from_secs(5)).await;
     Ok(())
 }
\ No newline at end of file
@@ -1,20 +1,13 @@
-use std::fs;
 use anyhow::Result;
-use base64::Engine;
 use tokio::try_join;
 use futures::future::try_join_all;
-use cdp_html_shot::{Browser, Tab};
+use cdp_html_shot::{Browser, CaptureOptions, Tab};
 
 async fn take_screenshot(tab: Tab, filename: &str) -> Result<()> {
     tab.set_content(HTML).await?;
     let element = tab.find_element("#title_and_result").await?;
-    let base64 = element.screenshot().await?;
+    element.save_to_file(format!("cache/{filename}"), CaptureOptions::new()).await?;
     tab.close().await?;
-    let img_data = base64::prelude::BASE64_STANDARD.decode(base64)?;
-
-    let dir = std::env::current_dir()?.join("cache");
-    fs::create_dir_all(&dir)?;
-    fs::write(dir.join(filename), img_data)?;
     Ok(())
 }
 
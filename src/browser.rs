@@ -1,23 +1,33 @@
 mod temp_dir;
 mod browser_utils;
 mod browser_config;
+mod launch_metrics;
 mod browser_builder;
+mod browser_options;
 
-use log::error;
 use std::sync::Arc;
-use serde_json::json;
+use std::time::{Duration, Instant};
+use std::collections::HashMap;
+use log::{debug, error, warn};
+use serde_json::{json, Value};
 use std::process::Child;
+use std::path::{Path, PathBuf};
 use tokio::sync::OnceCell;
 use temp_dir::CustomTempDir;
 use anyhow::{Context, Result};
 use browser_config::BrowserConfig;
 
 use crate::tab::Tab;
-use crate::CaptureOptions;
-use crate::transport::Transport;
-use crate::general_utils::next_id;
+use crate::{CaptureOptions, ImageFormat, PdfOptions, Watermark};
+use crate::transport::{Timeouts, Transport, DEFAULT_MAX_MESSAGE_SIZE};
+use crate::general_utils;
+#[cfg(feature = "image")]
+use crate::image_utils;
 use crate::transport_actor::TransportResponse;
-use crate::browser::browser_builder::BrowserBuilder;
+
+pub use launch_metrics::LaunchMetrics;
+pub use browser_builder::BrowserBuilder;
+pub use browser_options::BrowserOptions;
 
 /// The global browser instance.
 static mut BROWSER: OnceCell<Arc<Browser>> = OnceCell::const_new();
@@ -29,7 +39,9 @@ struct Process(pub Child, pub CustomTempDir);
 #[derive(Debug)]
 pub struct Browser {
     transport: Arc<Transport>,
-    process: Process,
+    process: Option<Process>,
+    executable_path: Option<PathBuf>,
+    launch_metrics: LaunchMetrics,
     is_closed: bool,
 }
 
@@ -52,132 +64,1029 @@ impl Browser {
     }
     ```
     */
-    pub async fn new() -> Result<Self> {
-        BrowserBuilder::new().build().await
+    pub async fn new() -> Result<Self> {
+        Self::new_with_options(BrowserOptions::default()).await
+    }
+
+    /// Create a new browser instance with a visible window.
+    pub async fn new_with_head() -> Result<Self> {
+        Self::new_with_options(BrowserOptions {
+            headless: false,
+            ..Default::default()
+        }).await
+    }
+
+    /**
+    Create a new browser instance from a set of [`BrowserOptions`].
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, BrowserOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new_with_options(BrowserOptions {
+            executable_path: Some("/usr/bin/chromium".into()),
+            lang: Some("fr-FR".to_string()),
+            ..Default::default()
+        }).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn new_with_options(options: BrowserOptions) -> Result<Self> {
+        BrowserBuilder::from_options(options).build().await
+    }
+
+    /**
+    Create a [`BrowserBuilder`] to configure the browser before launching it.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::builder()
+            .env("LANG", "ja_JP.UTF-8")
+            .env("FONTCONFIG_PATH", "/etc/fonts")
+            .build()
+            .await?;
+        Ok(())
+    }
+    ```
+    */
+    pub fn builder() -> BrowserBuilder {
+        BrowserBuilder::new()
+    }
+
+    /**
+    Launch a browser configured by `builder`, e.g. with custom arguments.
+
+    Equivalent to `builder.build()`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, BrowserBuilder};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let builder = BrowserBuilder::new()
+            .arg("--proxy-server=http://127.0.0.1:8080")
+            .remove_default_arg("--disable-extensions")
+            .headless(true);
+        let browser = Browser::launch_with(builder).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn launch_with(builder: BrowserBuilder) -> Result<Self> {
+        builder.build().await
+    }
+
+    /// Create a new headless browser instance using the Chrome executable at `path`.
+    pub async fn new_with_path(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new_with_options(BrowserOptions {
+            executable_path: Some(path.as_ref().to_path_buf()),
+            ..Default::default()
+        }).await
+    }
+
+    /// Create browser instance with custom configuration.
+    async fn create_browser(mut config: BrowserConfig) -> Result<Self> {
+        let executable_path = config.executable()?;
+        let mut launch_metrics = LaunchMetrics::default();
+
+        let (child, ws_url) = match Self::launch_process(&config, &executable_path, &mut launch_metrics).await {
+            Err(e) if config.headless && config.headless_fallback && e.is::<browser_utils::ExitedDuringStartup>() => {
+                warn!("Headless launch failed ({e:#}), retrying in headful mode");
+                config.headless = false;
+                Self::launch_process(&config, &executable_path, &mut launch_metrics).await?
+            }
+            res => res?,
+        };
+
+        let start = Instant::now();
+        let transport = Arc::new(Transport::new(
+            &ws_url,
+            config.timeouts,
+            config.keepalive_interval,
+            config.max_message_size,
+        ).await?);
+        launch_metrics.ws_connect = start.elapsed();
+
+        let start = Instant::now();
+        transport.send(json!({
+            "id": transport.next_id(),
+            "method": "Browser.getVersion",
+            "params": {}
+        })).await?;
+        launch_metrics.first_command = start.elapsed();
+
+        debug!("Browser launched: {:?}", launch_metrics);
+
+        Ok(Self {
+            transport,
+            process: Some(Process(child, config.temp_dir)),
+            executable_path: Some(executable_path),
+            launch_metrics,
+            is_closed: false,
+        })
+    }
+
+    /**
+    Connect to an already running browser whose DevTools WebSocket URL is stored in a file.
+
+    `path` may be a file containing the WebSocket URL, a `DevToolsActivePort` file as
+    Chrome writes it into its user data directory, or the user data directory itself.
+    This suits container setups where an orchestrator launches Chrome and shares the
+    endpoint through a volume.
+
+    The browser is not owned: closing or dropping the `Browser` only disconnects from it.
+    Use [`existing_tab`] to drive a page that is already open.
+
+    [`existing_tab`]: struct.Browser.html#method.existing_tab
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::connect_from_file("/shared/chrome-profile").await?;
+        let tab = browser.new_tab().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn connect_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let ws_url = browser_utils::ws_url_from_file(path.as_ref())?;
+        Self::connect(&ws_url).await
+    }
+
+    /**
+    Connect to an already running browser by its DevTools WebSocket URL, e.g.
+    `ws://127.0.0.1:9222/devtools/browser/<id>`.
+
+    The browser is not owned: closing or dropping the `Browser` only disconnects from it.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::connect("ws://127.0.0.1:9222/devtools/browser/<id>").await?;
+        let tab = browser.new_tab().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn connect(ws_url: &str) -> Result<Self> {
+        let mut launch_metrics = LaunchMetrics::default();
+
+        let start = Instant::now();
+        let transport = Arc::new(Transport::new(ws_url, Default::default(), None, DEFAULT_MAX_MESSAGE_SIZE).await?);
+        launch_metrics.ws_connect = start.elapsed();
+
+        let start = Instant::now();
+        transport.send(json!({
+            "id": transport.next_id(),
+            "method": "Browser.getVersion",
+            "params": {}
+        })).await?;
+        launch_metrics.first_command = start.elapsed();
+
+        debug!("Browser connected: {}", ws_url);
+
+        Ok(Self {
+            transport,
+            process: None,
+            executable_path: None,
+            launch_metrics,
+            is_closed: false,
+        })
+    }
+
+    /**
+    Connect to an already running browser started with `--remote-debugging-port`, given
+    the base URL of its DevTools HTTP endpoint, e.g. `http://chrome:9222`.
+
+    The WebSocket URL is discovered through `/json/version`, so it needn't be known in
+    advance. Like [`connect`], the browser is not owned.
+
+    Only plain `http://` endpoints are supported, which is all Chrome serves. The query is
+    made with a minimal built-in client that expects a `Content-Length` response, as Chrome
+    sends; proxies that rewrite it to chunked transfer encoding are not supported.
+
+    [`connect`]: struct.Browser.html#method.connect
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::connect_via_http("http://127.0.0.1:9222").await?;
+        let tab = browser.new_tab().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn connect_via_http(base: &str) -> Result<Self> {
+        let ws_url = browser_utils::ws_url_from_http(base, Timeouts::default().command).await?;
+        Self::connect(&ws_url).await
+    }
+
+    /// Spawn Chrome and discover its WebSocket URL, killing the process on failure.
+    async fn launch_process(
+        config: &BrowserConfig,
+        executable_path: &Path,
+        launch_metrics: &mut LaunchMetrics,
+    ) -> Result<(Child, String)> {
+        let start = Instant::now();
+        let mut child = browser_utils::spawn_chrome_process(config, executable_path)?;
+        launch_metrics.spawn = start.elapsed();
+
+        let start = Instant::now();
+        let stderr = child.stderr.take().context("Failed to get stderr")?;
+        match browser_utils::get_websocket_url(stderr, config.temp_dir.path(), config.discovery_timeout).await {
+            Ok(ws_url) => {
+                launch_metrics.ws_discovery = start.elapsed();
+                Ok((child, ws_url))
+            }
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                Err(e)
+            }
+        }
+    }
+
+    /**
+    Launch a browser that is not killed when this process exits, and return its
+    DevTools WebSocket URL.
+
+    Neither the process nor its user data directory are cleaned up, so the browser can be
+    shared by other processes connecting to the URL. Stop it with `Browser.close`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let ws_url = Browser::spawn_detached().await?;
+        println!("{ws_url}");
+        Ok(())
+    }
+    ```
+    */
+    pub async fn spawn_detached() -> Result<String> {
+        BrowserBuilder::new().spawn_detached().await
+    }
+
+    async fn spawn_detached_process(config: BrowserConfig) -> Result<String> {
+        let executable_path = config.executable()?;
+        let (_child, ws_url) = Self::launch_process(&config, &executable_path, &mut LaunchMetrics::default()).await?;
+
+        let user_data_dir = config.temp_dir.persist();
+        debug!("Detached browser {} using {}", ws_url, user_data_dir.display());
+
+        Ok(ws_url)
+    }
+
+    /// The user data directory of this browser, or `None` if it was connected to rather
+    /// than launched.
+    ///
+    /// It is removed when the browser is closed, but may be left behind if the process
+    /// is killed; see [`cleanup_orphaned_temp_dirs`].
+    ///
+    /// [`cleanup_orphaned_temp_dirs`]: struct.Browser.html#method.cleanup_orphaned_temp_dirs
+    pub fn temp_dir_path(&self) -> Option<&Path> {
+        self.process.as_ref().map(|process| process.1.path())
+    }
+
+    /**
+    Remove user data directories left behind in `base` by browsers that were not
+    closed cleanly, e.g. after a crash.
+
+    Directories named `cdp-html-shot_*` or `cdp-shot_*` last modified more than
+    `older_than` ago are removed; pick a threshold longer than any browser you expect
+    to be running. Browsers launched by this crate use `./temp` as the base directory.
+    Returns the number of directories removed.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use std::time::Duration;
+    use anyhow::Result;
+
+    fn main() -> Result<()> {
+        let removed = Browser::cleanup_orphaned_temp_dirs("temp", Duration::from_secs(24 * 3600))?;
+        println!("Removed {removed} stale directories");
+        Ok(())
+    }
+    ```
+    */
+    pub fn cleanup_orphaned_temp_dirs(base: impl AsRef<Path>, older_than: Duration) -> Result<usize> {
+        temp_dir::remove_stale(base.as_ref(), &["cdp-html-shot", "cdp-shot"], older_than)
+    }
+
+    /// Timings of the launch phases of this browser.
+    ///
+    /// Only `ws_connect` and `first_command` are measured for a connected browser.
+    pub fn launch_metrics(&self) -> LaunchMetrics {
+        self.launch_metrics
+    }
+
+    /// The path of the Chrome executable this browser was launched with,
+    /// or `None` if it was connected to rather than launched.
+    pub fn executable_path(&self) -> Option<&Path> {
+        self.executable_path.as_deref()
+    }
+
+    /**
+    Create a new tab.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn new_tab(&self) -> Result<Tab> {
+        Tab::new(self.transport.clone()).await
+    }
+
+    /**
+    Attach to the first page already open in the browser, if any.
+
+    Useful after connecting to a remote browser that already has the page to drive open,
+    instead of creating an extra tab with [`new_tab`].
+
+    [`new_tab`]: struct.Browser.html#method.new_tab
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::connect_from_file("/shared/chrome-profile").await?;
+        let tab = match browser.existing_tab().await? {
+            Some(tab) => tab,
+            None => browser.new_tab().await?,
+        };
+        Ok(())
+    }
+    ```
+    */
+    pub async fn existing_tab(&self) -> Result<Option<Tab>> {
+        match self.page_target_ids().await?.first() {
+            Some(target_id) => Ok(Some(Tab::attach(self.transport.clone(), target_id).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /**
+    Evaluate a JavaScript expression in every open page and return each result with the
+    id of its page target.
+
+    Useful for maintenance of a shared browser with many long-lived tabs, e.g. clearing
+    storage everywhere. Promises are awaited; the first failing evaluation fails the call.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        for (target_id, url) in browser.broadcast_eval("location.href").await? {
+            println!("{target_id}: {url}");
+        }
+        Ok(())
+    }
+    ```
+    */
+    pub async fn broadcast_eval(&self, expression: &str) -> Result<Vec<(String, Value)>> {
+        let mut results = Vec::new();
+
+        for target_id in self.page_target_ids().await? {
+            let tab = Tab::attach(self.transport.clone(), &target_id).await?;
+            let res = tab.evaluate(expression).await;
+
+            // Only detach, as closing the tab would close the page.
+            self.transport.send(json!({
+                "id": self.transport.next_id(),
+                "method": "Target.detachFromTarget",
+                "params": {
+                    "sessionId": tab.session_id
+                }
+            })).await?;
+
+            let value = res.with_context(|| format!("Evaluation failed in target {target_id}"))?;
+            results.push((target_id, value));
+        }
+
+        Ok(results)
+    }
+
+    /// The ids of all page targets, i.e. open tabs.
+    async fn page_target_ids(&self) -> Result<Vec<String>> {
+        let TransportResponse::Response(res) = self.transport.send(json!({
+            "id": self.transport.next_id(),
+            "method": "Target.getTargets",
+            "params": {}
+        })).await? else { panic!() };
+
+        Ok(res
+            .result["targetInfos"]
+            .as_array()
+            .context("Failed to get targetInfos")?
+            .iter()
+            .filter(|info| info["type"].as_str() == Some("page"))
+            .filter_map(|info| info["targetId"].as_str().map(String::from))
+            .collect())
+    }
+
+    /**
+    Wait for a new page to open, e.g. a popup from `window.open` or a `target=_blank`
+    link, and attach to it.
+
+    Only pages created after the call count, so start waiting before triggering the
+    popup. Fails if no page opens within `timeout_ms`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<a href='https://example.com' target='_blank'>Open</a>").await?;
+
+        let (popup, _) = tokio::try_join!(
+            browser.wait_for_new_target(10_000),
+            tab.evaluate("document.querySelector('a').click()"),
+        )?;
+        popup.wait_for_selector("h1", 10_000).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn wait_for_new_target(&self, timeout_ms: u64) -> Result<Tab> {
+        let mut events = self.transport.subscribe("", "Target.targetCreated").await?;
+
+        let TransportResponse::Response(res) = self.transport.send(json!({
+            "id": self.transport.next_id(),
+            "method": "Target.getTargets",
+            "params": {}
+        })).await? else { panic!() };
+
+        // Discovery reports the existing targets too, which must be skipped.
+        let existing: Vec<String> = res
+            .result["targetInfos"]
+            .as_array()
+            .context("Failed to get targetInfos")?
+            .iter()
+            .filter_map(|info| info["targetId"].as_str().map(String::from))
+            .collect();
+
+        self.transport.send(json!({
+            "id": self.transport.next_id(),
+            "method": "Target.setDiscoverTargets",
+            "params": {
+                "discover": true
+            }
+        })).await?;
+
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(timeout_ms);
+
+        loop {
+            let event = tokio::time::timeout_at(deadline, events.recv())
+                .await
+                .map_err(|_| anyhow::anyhow!("Timeout while waiting for a new page"))?
+                .context("Connection closed while waiting for a new page")?;
+
+            let info = &event.params["targetInfo"];
+            let Some(target_id) = info["targetId"].as_str() else {
+                continue;
+            };
+
+            if info["type"].as_str() == Some("page") && !existing.iter().any(|id| id == target_id) {
+                return Tab::attach(self.transport.clone(), target_id).await;
+            }
+        }
+    }
+
+    /**
+    Close the initial tab created when the browser starts.
+
+    # Warning
+    Only in headless mode, otherwise it will close the entire browser.
+    */
+    pub async fn close_init_tab(&self) -> Result<()> {
+        let TransportResponse::Response(res) = self.transport.send(json!({
+            "id": self.transport.next_id(),
+            "method": "Target.getTargets",
+            "params": {}
+        })).await? else { panic!() };
+
+        let target_id = res
+            .result["targetInfos"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|info| {
+                info["type"].as_str().unwrap() == "page"
+            }).unwrap()["targetId"]
+            .as_str()
+            .unwrap();
+
+        self.transport.send(json!({
+            "id": self.transport.next_id(),
+            "method": "Target.closeTarget",
+            "params": {
+                "targetId": target_id
+            }
+        })).await?;
+
+        Ok(())
+    }
+
+    /**
+    Basic version: Capture a screenshot of an HTML element.
+
+    Returns the base64-encoded image data (JPEG format).
+
+    If you need more control over the capture process, use [`capture_html_with_options`].
+
+    # Arguments
+    - `html`: The HTML content
+    - `selector`: The CSS selector of the element to capture
+
+    [`capture_html_with_options`]: struct.Browser.html#method.capture_html_with_options
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let base64 = browser.capture_html("<h1>Hello world!</h1>", "h1").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_html(&self, html: impl AsRef<str>, selector: &str) -> Result<String> {
+        let data = self.capture_html_bytes(html, selector).await?;
+
+        Ok(general_utils::encode_base64(&data))
+    }
+
+    /**
+    Like [`capture_html`], but return the decoded image bytes (JPEG format) instead of base64.
+
+    [`capture_html`]: struct.Browser.html#method.capture_html
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let jpeg = browser.capture_html_bytes("<h1>Hello world!</h1>", "h1").await?;
+        std::fs::write("hello.jpeg", jpeg)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_html_bytes(&self, html: impl AsRef<str>, selector: &str) -> Result<Vec<u8>> {
+        let tab = self.new_tab().await?;
+
+        let res = async {
+            tab.set_content(html).await?;
+            tab.find_element(selector).await?.screenshot_bytes(CaptureOptions::new()).await
+        }.await;
+
+        tab.close().await?;
+
+        res
+    }
+
+    /**
+    Render `text` into a [`Watermark`], styled with the CSS declarations in `style`.
+
+    The text is laid out by the browser in a new tab and captured as a PNG with a
+    transparent background, so any font the browser has can be used. Render it once and
+    reuse the watermark for all captures.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions, WatermarkPosition};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let watermark = browser
+            .text_watermark("© Example", "font: bold 24px sans-serif; color: white")
+            .await?
+            .with_position(WatermarkPosition::BottomRight)
+            .with_opacity(0.7);
+
+        let options = CaptureOptions::new().with_watermark(watermark);
+        let base64 = browser.capture_html_with_options("<h1>Hello world!</h1>", "h1", options).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn text_watermark(&self, text: &str, style: &str) -> Result<Watermark> {
+        let mut html = String::from("<body style=\"margin: 0\"><span id=\"watermark\" style=\"display: inline-block; white-space: pre; ");
+        general_utils::escape_html(style, &mut html);
+        html.push_str("\">");
+        general_utils::escape_html(text, &mut html);
+        html.push_str("</span></body>");
+
+        let tab = self.new_tab().await?;
+
+        let res = async {
+            tab.set_content(html).await?;
+            let options = CaptureOptions::new()
+                .with_format(ImageFormat::Png)
+                .with_omit_background(true);
+            tab.find_element("#watermark").await?.screenshot_bytes(options).await
+        }.await;
+
+        tab.close().await?;
+
+        Ok(Watermark::new(res?))
+    }
+
+    /**
+    Load `html` into a new tab and print it to PDF.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, PdfOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let pdf = browser.capture_html_pdf("<h1>Invoice #42</h1>", PdfOptions::new()).await?;
+        std::fs::write("invoice.pdf", pdf)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_html_pdf(&self, html: impl AsRef<str>, options: PdfOptions) -> Result<Vec<u8>> {
+        let tab = self.new_tab().await?;
+
+        let res = async {
+            tab.set_content(html).await?;
+            tab.print_to_pdf(options).await
+        }.await;
+
+        tab.close().await?;
+        res
+    }
+
+    /**
+    Fill `template` with `data` and capture the element matching `selector`.
+
+    Each `{{key}}` placeholder is replaced with the value of `key`, HTML-escaped so
+    values containing `<`, `&` or quotes render as text and can't break the markup.
+    A placeholder without a value in `data` is an error.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use std::collections::HashMap;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let template = "<div id='card'><h1>{{ name }}</h1><p>ID: {{id}}</p></div>";
+
+        for (name, id) in [("Alice", "1"), ("Bob <admin>", "2")] {
+            let data = HashMap::from([("name", name), ("id", id)]);
+            let base64 = browser.capture_template(template, &data, "#card", CaptureOptions::new()).await?;
+        }
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_template(
+        &self,
+        template: &str,
+        data: &HashMap<&str, &str>,
+        selector: &str,
+        options: CaptureOptions,
+    ) -> Result<String> {
+        let html = general_utils::render_template(template, data)?;
+
+        self.capture_html_with_options(html, selector, options).await
+    }
+
+    /**
+    Capture an HTML element as PNG and compare it pixel by pixel with a baseline image.
+
+    `baseline` is an encoded image, e.g. the bytes of a PNG file from a previous run.
+    A pixel counts as different when any channel differs by more than `tolerance`
+    (0.0-1.0) of the channel range, so a small tolerance absorbs antialiasing noise.
+
+    Requires the `image` feature.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let baseline = std::fs::read("baseline.png")?;
+        let diff = browser.capture_and_compare("<h1>Hello world!</h1>", "h1", &baseline, 0.02).await?;
+        if diff.diff_fraction > 0.001 {
+            std::fs::write("diff.png", &diff.diff_image)?;
+        }
+        Ok(())
+    }
+    ```
+    */
+    #[cfg(feature = "image")]
+    pub async fn capture_and_compare(
+        &self,
+        html: impl AsRef<str>,
+        selector: &str,
+        baseline: &[u8],
+        tolerance: f64,
+    ) -> Result<image_utils::DiffResult> {
+        let options = CaptureOptions::new().with_format(crate::ImageFormat::Png);
+        let tab = self.load_content(html, &options).await?;
+
+        let res = async {
+            tab.find_element(selector).await?.screenshot_bytes(options).await
+        }.await;
+
+        tab.close().await?;
+        let capture = res?;
+
+        image_utils::diff_images(&capture, baseline, tolerance)
     }
 
-    /// Create a new browser instance with a visible window.
-    pub async fn new_with_head() -> Result<Self> {
-        BrowserBuilder::new()
-            .headless(false)
-            .build()
-            .await
+    /// Open a new tab with `html` loaded and ready to capture according to `options`.
+    async fn load_content(&self, html: impl AsRef<str>, options: &CaptureOptions) -> Result<Tab> {
+        let tab = self.new_tab().await?;
+
+        if let Err(e) = Self::prepare_tab(&tab, html, options).await {
+            tab.close().await?;
+            return Err(e);
+        }
+
+        Ok(tab)
     }
 
-    /// Create browser instance with custom configuration.
-    async fn create_browser(config: BrowserConfig) -> Result<Self> {
-        let mut child = browser_utils::spawn_chrome_process(&config)?;
-        let ws_url = browser_utils::get_websocket_url(
-            child.stderr.take().context("Failed to get stderr")?
-        ).await?;
+    /// Load `html` into `tab` and get it ready to capture according to `options`.
+    async fn prepare_tab(tab: &Tab, html: impl AsRef<str>, options: &CaptureOptions) -> Result<()> {
+        if let Some(hide) = options.hide_scrollbars {
+            tab.set_scrollbars_visible(!hide).await?;
+        }
 
-        Ok(Self {
-            transport: Arc::new(Transport::new(&ws_url).await?),
-            process: Process(child, config.temp_dir),
-            is_closed: false,
-        })
+        tab.set_content(html).await?;
+
+        Self::wait_until_ready(tab, options).await
+    }
+
+    /// Wait for the readiness conditions of `options` once the content of `tab` is loaded.
+    async fn wait_until_ready(tab: &Tab, options: &CaptureOptions) -> Result<()> {
+        if let Some(idle_ms) = options.network_idle {
+            let timeout_ms = tab.timeouts().navigation.as_millis() as u64;
+            tab.wait_for_network_idle(idle_ms, timeout_ms).await?;
+        }
+
+        if let Some(timeout_ms) = options.wait_for_images {
+            tab.wait_for_images(timeout_ms).await?;
+        }
+
+        if let Some(script) = &options.ready_script {
+            tab.evaluate_within(script, tab.timeouts().navigation).await
+                .context("Ready script failed")?;
+        }
+
+        Ok(())
     }
 
     /**
-    Create a new tab.
+    Advanced version: Capture a screenshot of an HTML element with additional options.
+
+    # Arguments
+    - `html`: The HTML content
+    - `selector`: The CSS selector of the element to capture
+    - `options`: Configuration options for the capture
 
     # Example
     ```no_run
-    use cdp_html_shot::Browser;
+    use cdp_html_shot::{Browser, CaptureOptions};
     use anyhow::Result;
 
     #[tokio::main]
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
-        let tab = browser.new_tab().await?;
+        let options = CaptureOptions::new()
+            .with_raw_png(true);
+
+        let base64 = browser
+            .capture_html_with_options(
+                "<h1>Hello world!</h1>",
+                "h1",
+                options
+            ).await?;
         Ok(())
     }
     ```
     */
-    pub async fn new_tab(&self) -> Result<Tab> {
-        Tab::new(self.transport.clone()).await
+    pub async fn capture_html_with_options(
+        &self,
+        html: impl AsRef<str>,
+        selector: &str,
+        options: CaptureOptions,
+    ) -> Result<String> {
+        let tab = self.load_content(html, &options).await?;
+
+        let res = async {
+            tab.find_element(selector).await?.screenshot_with_options(options).await
+        }.await;
+
+        tab.close().await?;
+
+        res
     }
 
     /**
-    Close the initial tab created when the browser starts.
+    Capture an HTML element like [`capture_html_with_options`], but keep the tab open
+    and return it along with the decoded image data.
 
-    # Warning
-    Only in headless mode, otherwise it will close the entire browser.
+    The tab can then be inspected further, e.g. to extract text or capture more elements.
+
+    [`capture_html_with_options`]: struct.Browser.html#method.capture_html_with_options
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let html = "<h1>Title</h1><p>Body</p>";
+
+        let (image, tab) = browser.capture_html_keep_tab(html, "h1", CaptureOptions::new()).await?;
+        let paragraph = tab.find_element("p").await?.screenshot().await?;
+        tab.close().await?;
+        Ok(())
+    }
+    ```
     */
-    pub async fn close_init_tab(&self) -> Result<()> {
-        let TransportResponse::Response(res) = self.transport.send(json!({
-            "id": next_id(),
-            "method": "Target.getTargets",
-            "params": {}
-        })).await? else { panic!() };
+    pub async fn capture_html_keep_tab(
+        &self,
+        html: impl AsRef<str>,
+        selector: &str,
+        options: CaptureOptions,
+    ) -> Result<(Vec<u8>, Tab)> {
+        let tab = self.load_content(html, &options).await?;
 
-        let target_id = res
-            .result["targetInfos"]
-            .as_array()
-            .unwrap()
-            .iter()
-            .find(|info| {
-                info["type"].as_str().unwrap() == "page"
-            }).unwrap()["targetId"]
-            .as_str()
-            .unwrap();
+        let res = async {
+            tab.find_element(selector).await?.screenshot_bytes(options).await
+        }.await;
 
-        self.transport.send(json!({
-            "id": next_id(),
-            "method": "Target.closeTarget",
-            "params": {
-                "targetId": target_id
+        match res {
+            Ok(data) => Ok((data, tab)),
+            Err(e) => {
+                tab.close().await?;
+                Err(e)
             }
-        })).await?;
+        }
+    }
+
+    /**
+    Capture the first element of an HTML page matching any of `selectors`.
+
+    Tries the selectors in order and fails only if none of them match. Returns the
+    selector that matched along with the base64-encoded image.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let html = "<div class='card-b'>Hello</div>";
 
+        let (selector, base64) = browser
+            .capture_html_any(html, &[".card-a", ".card-b"], CaptureOptions::new())
+            .await?;
+        println!("matched {selector}");
         Ok(())
     }
+    ```
+    */
+    pub async fn capture_html_any<'s>(
+        &self,
+        html: impl AsRef<str>,
+        selectors: &[&'s str],
+        options: CaptureOptions,
+    ) -> Result<(&'s str, String)> {
+        let tab = self.load_content(html, &options).await?;
 
-    /**
-    Basic version: Capture a screenshot of an HTML element.
+        let res = async {
+            let (selector, element) = tab.find_element_any(selectors).await?;
+            let base64 = element.screenshot_with_options(options).await?;
+            Ok::<_, anyhow::Error>((selector, base64))
+        }.await;
 
-    Returns the base64-encoded image data (JPEG format).
+        tab.close().await?;
 
-    If you need more control over the capture process, use [`capture_html_with_options`].
+        res
+    }
 
-    # Arguments
-    - `html`: The HTML content
-    - `selector`: The CSS selector of the element to capture
+    /**
+    Capture a screenshot of an HTML element in an existing tab.
+
+    Works like [`capture_html_with_options`], but loads the content into `tab` and leaves
+    it open, so the tab can be prepared beforehand (cookies, headers, viewport) and reused.
 
     [`capture_html_with_options`]: struct.Browser.html#method.capture_html_with_options
 
     # Example
     ```no_run
-    use cdp_html_shot::Browser;
+    use cdp_html_shot::{Browser, CaptureOptions, Viewport};
     use anyhow::Result;
 
     #[tokio::main]
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
-        let base64 = browser.capture_html("<h1>Hello world!</h1>", "h1").await?;
+        let tab = browser.new_tab().await?;
+        tab.set_viewport(&Viewport::new(800, 600)).await?;
+
+        let base64 = browser
+            .capture_on_tab(
+                &tab,
+                "<h1>Hello world!</h1>",
+                "h1",
+                CaptureOptions::new()
+            ).await?;
         Ok(())
     }
     ```
     */
-    pub async fn capture_html(&self, html: &str, selector: &str) -> Result<String> {
-        let tab = self.new_tab().await?;
-
-        tab.set_content(html).await?;
+    pub async fn capture_on_tab(
+        &self,
+        tab: &Tab,
+        html: impl AsRef<str>,
+        selector: &str,
+        options: CaptureOptions,
+    ) -> Result<String> {
+        Self::prepare_tab(tab, html, &options).await?;
 
         let element = tab.find_element(selector).await?;
-        let base64 = element.screenshot().await?;
 
-        tab.close().await?;
-        Ok(base64)
+        element.screenshot_with_options(options).await
     }
 
     /**
-    Advanced version: Capture a screenshot of an HTML element with additional options.
+    Capture several named elements of an HTML page, rendering the page only once.
 
-    # Arguments
-    - `html`: The HTML content
-    - `selector`: The CSS selector of the element to capture
-    - `options`: Configuration options for the capture
+    Each entry of `selectors` is a `(name, selector)` pair. Returns a map from each
+    name to the decoded image data of its element.
 
     # Example
     ```no_run
@@ -187,46 +1096,105 @@ impl Browser {
     #[tokio::main]
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
-        let options = CaptureOptions::new()
-            .with_raw_png(true);
+        let images = browser
+            .capture_html_named(
+                "<h1>Report</h1><table id='data'></table>",
+                &[("title", "h1"), ("data", "#data")],
+                CaptureOptions::new()
+            ).await?;
 
-        let base64 = browser
-            .capture_html_with_options(
-                "<h1>Hello world!</h1>",
-                "h1",
-                options
+        std::fs::write("title.jpeg", &images["title"])?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_html_named(
+        &self,
+        html: impl AsRef<str>,
+        selectors: &[(&str, &str)],
+        options: CaptureOptions,
+    ) -> Result<HashMap<String, Vec<u8>>> {
+        let tab = self.load_content(html, &options).await?;
+
+        let res = async {
+            let mut images = HashMap::with_capacity(selectors.len());
+            for (name, selector) in selectors {
+                let element = tab.find_element(selector).await
+                    .with_context(|| format!("Failed to find {} ({})", name, selector))?;
+                images.insert(name.to_string(), element.screenshot_bytes(options.clone()).await?);
+            }
+            Ok::<_, anyhow::Error>(images)
+        }.await;
+
+        tab.close().await?;
+
+        res
+    }
+
+    /**
+    Capture a screenshot of an element of a live web page and write it to `path`.
+
+    Navigates a new tab to `url`, waits for the load event, the readiness conditions of
+    `options` such as [`CaptureOptions::with_wait_for_network_idle`] and for `selector`
+    to appear, then captures the element like [`Element::screenshot_to_file`], inferring
+    the format from the file extension unless `options` sets one.
+
+    [`CaptureOptions::with_wait_for_network_idle`]: struct.CaptureOptions.html#method.with_wait_for_network_idle
+
+    [`Element::screenshot_to_file`]: struct.Element.html#method.screenshot_to_file
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        browser
+            .capture_url_to_file(
+                "https://www.rust-lang.org/",
+                "main",
+                "rust-lang.png",
+                CaptureOptions::new().with_wait_for_network_idle(500)
             ).await?;
         Ok(())
     }
     ```
     */
-    pub async fn capture_html_with_options(
+    pub async fn capture_url_to_file(
         &self,
-        html: &str,
+        url: &str,
         selector: &str,
+        path: impl AsRef<Path>,
         options: CaptureOptions,
-    ) -> Result<String> {
+    ) -> Result<()> {
         let tab = self.new_tab().await?;
 
-        tab.set_content(html).await?;
-
-        let element = tab.find_element(selector).await?;
+        let res = async {
+            // Requests started by the navigation are only seen by the network-idle wait
+            // while a recording is running.
+            if options.network_idle.is_some() {
+                tab.start_network_recording().await?;
+            }
+            tab.goto_and_wait(url).await?;
+            Self::wait_until_ready(&tab, &options).await?;
 
-        let base64 = if options.raw_png {
-            element.raw_screenshot().await?
-        } else {
-            element.screenshot().await?
-        };
+            let timeout_ms = tab.timeouts().navigation.as_millis() as u64;
+            let element = tab.wait_for_selector(selector, timeout_ms).await?;
+            element.screenshot_to_file(path, options).await
+        }.await;
 
         tab.close().await?;
 
-        Ok(base64)
+        res
     }
 
     /**
     Close the browser.
 
     This will kill the browser process and clean up temporary files.
+    A browser that was connected to rather than launched is only disconnected from.
 
     Normally, this method does not need to be called manually,
     because it will be called automatically when the `Browser` instance is destroyed.
@@ -249,21 +1217,62 @@ impl Browser {
             return Ok(());
         }
 
-        Arc::get_mut(&mut self.transport)
-            .unwrap()
-            .shutdown();
+        let Some(process) = self.process.as_mut() else {
+            self.transport.shutdown(false);
+            self.is_closed = true;
+            return Ok(());
+        };
+
+        self.transport.shutdown(true);
 
-        self.process.0
+        process.0
             .kill()
-            .and_then(|_| self.process.0.wait())
+            .and_then(|_| process.0.wait())
             .context("Failed to kill the browser process")?;
 
-        self.process.1
+        process.1
             .cleanup()?;
 
         self.is_closed = true;
         Ok(())
     }
+
+    /**
+    Disconnect from the browser without closing it.
+
+    Only the local WebSocket is closed: `Browser.close` is never sent, so a browser
+    shared with other clients keeps running. A browser launched by this instance is left
+    running too, together with its user data directory, like one from [`spawn_detached`].
+
+    [`spawn_detached`]: struct.Browser.html#method.spawn_detached
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let mut browser = Browser::connect_from_file("/shared/chrome-profile").await?;
+        browser.disconnect();
+        Ok(())
+    }
+    ```
+    */
+    pub fn disconnect(&mut self) {
+        if self.is_closed {
+            return;
+        }
+
+        self.transport.shutdown(false);
+
+        if let Some(Process(child, temp_dir)) = self.process.take() {
+            let user_data_dir = temp_dir.persist();
+            debug!("Left browser {} running using {}", child.id(), user_data_dir.display());
+        }
+
+        self.is_closed = true;
+    }
 }
 
 impl Browser {
@@ -306,6 +1315,54 @@ impl Browser {
         }
     }
 
+    /**
+    Get the global Browser instance, launching it with the Chrome executable at `path`.
+
+    The path is only used if the instance doesn't exist yet. If the live instance was
+    launched with a different executable, a warning is logged and the live instance
+    is returned as is; check [`executable_path`] to see which one is in use.
+
+    [`executable_path`]: struct.Browser.html#method.executable_path
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::instance_with_path("/usr/bin/chromium").await;
+        let tab = browser.new_tab().await?;
+
+        Browser::close_instance();
+        Ok(())
+    }
+    ```
+    */
+    pub async fn instance_with_path(path: impl AsRef<Path>) -> Arc<Browser> {
+        let path = path.as_ref();
+
+        unsafe {
+            let browser = (*std::ptr::addr_of!(BROWSER))
+                .get_or_init(|| async {
+                    let browser = Browser::new_with_path(path).await.unwrap();
+                    browser.close_init_tab().await.unwrap();
+                    Arc::new(browser)
+                })
+                .await;
+
+            if let Some(executable_path) = browser.executable_path().filter(|p| *p != path) {
+                warn!(
+                    "Browser instance is already running with {}, ignoring requested path {}",
+                    executable_path.display(),
+                    path.display()
+                );
+            }
+
+            browser.clone()
+        }
+    }
+
     /**
     Close the global Browser instance.
 
@@ -1,6 +1,10 @@
 use anyhow::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::ffi::OsString;
 
 use crate::Browser;
+use crate::browser::BrowserOptions;
 use crate::browser::browser_config::BrowserConfig;
 
 /// Builder for configuring and creating Browser instances.
@@ -17,12 +21,166 @@ impl BrowserBuilder {
         }
     }
 
+    /// Create a BrowserBuilder configured from `options`.
+    pub fn from_options(options: BrowserOptions) -> Self {
+        let mut builder = Self::new()
+            .headless(options.headless)
+            .command_timeout(options.command_timeout)
+            .navigation_timeout(options.navigation_timeout);
+
+        builder.config.executable_path = options.executable_path;
+        builder.config.sandbox = options.sandbox;
+        builder.config.lang = options.lang;
+        builder.config.extra_args = options.args;
+        builder
+    }
+
     /// Set whether the browser should run in headless mode.
     pub fn headless(mut self, headless: bool) -> Self {
         self.config.headless = headless;
         self
     }
 
+    /// Set whether to retry once in headful mode when headless Chrome exits during startup.
+    ///
+    /// Disabled by default, as the retry opens a visible window on a desktop. Some minimal
+    /// Linux images can't run headless Chrome but can run headful Chrome under Xvfb; the
+    /// switch is logged as a warning. Other failures, such as timeouts, are not retried.
+    pub fn headless_fallback(mut self, fallback: bool) -> Self {
+        self.config.headless_fallback = fallback;
+        self
+    }
+
+    /// Set whether to hide scrollbars in all tabs. Enabled by default.
+    ///
+    /// Disable it to get the layout of a desktop browser with visible scrollbars, e.g.
+    /// when `scrollbar-gutter: stable` must reserve space to match real screenshots.
+    /// Scrollbars can still be hidden per capture with `CaptureOptions::with_hide_scrollbars`.
+    pub fn hide_scrollbars(mut self, hide: bool) -> Self {
+        self.config.hide_scrollbars = hide;
+        self
+    }
+
+    /// Set how long to wait for Chrome to report its DevTools WebSocket URL.
+    /// Defaults to 30 seconds.
+    pub fn discovery_timeout(mut self, timeout: Duration) -> Self {
+        self.config.discovery_timeout = timeout;
+        self
+    }
+
+    /// Set whether to keep Chrome's sandbox enabled.
+    ///
+    /// By default, `--no-sandbox` and `--no-zygote` are passed when running as root or,
+    /// on Linux, outside a desktop session, which is what containers need. The sandbox is
+    /// kept otherwise, including on macOS and Windows.
+    pub fn sandbox(mut self, sandbox: bool) -> Self {
+        self.config.sandbox = Some(sandbox);
+        self
+    }
+
+    /// Set the browser UI language and default `Accept-Language`, e.g. `"de-DE"`.
+    ///
+    /// Sites that localize by browser language render in this language, as read by
+    /// `navigator.language`. A comma-separated list such as `"fr-FR,fr,en"` sets the
+    /// full `Accept-Language` preference order.
+    pub fn lang(mut self, lang: &str) -> Self {
+        self.config.lang = Some(lang.to_string());
+        self
+    }
+
+    /// Set the path of the Chrome executable to launch instead of auto-detecting one.
+    pub fn executable(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.executable_path = Some(path.into());
+        self
+    }
+
+    /// Set both the command and the navigation timeout, e.g. to fail fast in tests or to
+    /// be patient on a slow CI machine.
+    ///
+    /// Tabs can override them with `Tab::set_default_timeout` and
+    /// `Tab::set_default_navigation_timeout`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeouts.command = timeout;
+        self.config.timeouts.navigation = timeout;
+        self
+    }
+
+    /// Set how long to wait for the reply to a single command. Defaults to 10 seconds.
+    pub fn command_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeouts.command = timeout;
+        self
+    }
+
+    /// Set how long to wait for a page to load, e.g. in `Tab::set_content` and
+    /// `Tab::reload`. Defaults to 60 seconds.
+    pub fn navigation_timeout(mut self, timeout: Duration) -> Self {
+        self.config.timeouts.navigation = timeout;
+        self
+    }
+
+    /// Send a WebSocket ping every `interval` so an idle connection isn't dropped.
+    ///
+    /// Disabled by default. Useful for long-lived instances that sit idle between captures.
+    pub fn keepalive_interval(mut self, interval: Duration) -> Self {
+        self.config.keepalive_interval = Some(interval);
+        self
+    }
+
+    /// Set the largest WebSocket message accepted from the browser, in bytes.
+    ///
+    /// Defaults to 256 MiB. Captures larger than this make the connection fail, so raise
+    /// it for very large full-page PNGs, or lower it to bound memory use.
+    pub fn max_message_size(mut self, bytes: usize) -> Self {
+        self.config.max_message_size = bytes;
+        self
+    }
+
+    /// Set an environment variable for the Chrome process, e.g. `DISPLAY` or `LANG`.
+    ///
+    /// The process otherwise inherits the environment of the current process.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.config.envs.push((key.into(), value.into()));
+        self
+    }
+
+    /// Set multiple environment variables for the Chrome process.
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        self.config.envs.extend(vars.into_iter().map(|(key, value)| (key.into(), value.into())));
+        self
+    }
+
+    /// Add a command line argument for Chrome, e.g. `--proxy-server=http://proxy:8080`.
+    ///
+    /// Arguments are passed after the defaults, so for most switches they take precedence.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.config.extra_args.push(arg.into());
+        self
+    }
+
+    /// Add multiple command line arguments for Chrome.
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Remove one of the default arguments, e.g. `--disable-extensions` to load an extension.
+    ///
+    /// Matches the switch with any value, so `--js-flags` removes `--js-flags=...`.
+    /// Arguments added with [`arg`](Self::arg) are not affected.
+    pub fn remove_default_arg(mut self, arg: &str) -> Self {
+        self.config.removed_args.push(arg.to_string());
+        self
+    }
+
     /// Configure additional options here as needed.
     // pub fn with_option(mut self, option: Option) -> Self { ... }
 
@@ -30,6 +188,13 @@ impl BrowserBuilder {
     pub async fn build(self) -> Result<Browser> {
         Browser::create_browser(self.config).await
     }
+
+    /// Launch a browser that outlives this process and return its DevTools WebSocket URL.
+    ///
+    /// See [`Browser::spawn_detached`].
+    pub async fn spawn_detached(self) -> Result<String> {
+        Browser::spawn_detached_process(self.config).await
+    }
 }
 
 impl Default for BrowserBuilder {
@@ -1,4 +1,6 @@
 use std::net;
+use std::ffi::OsString;
+use std::time::Duration;
 use which::which;
 use std::path::{Path, PathBuf};
 use rand::prelude::SliceRandom;
@@ -7,11 +9,11 @@ use anyhow::{anyhow, Context, Result};
 #[cfg(windows)]
 use winreg::{RegKey, enums::HKEY_LOCAL_MACHINE};
 
+use crate::transport::{Timeouts, DEFAULT_MAX_MESSAGE_SIZE};
 use crate::browser::temp_dir::CustomTempDir;
 
-static DEFAULT_ARGS: [&str; 37] = [
+static DEFAULT_ARGS: [&str; 35] = [
     // System Settings
-    "--no-sandbox",
     "--no-first-run",
     "--no-default-browser-check",
     "--no-experiments",
@@ -61,7 +63,6 @@ static DEFAULT_ARGS: [&str; 37] = [
 
     // Performance
     "--disable-ipc-flooding-protection",
-    "--no-zygote",
 
     // Logging
     // "--enable-logging=stderr"
@@ -70,8 +71,19 @@ static DEFAULT_ARGS: [&str; 37] = [
 pub(crate) struct BrowserConfig {
     debug_port: u16,
     pub(crate) headless: bool,
+    pub(crate) headless_fallback: bool,
+    pub(crate) hide_scrollbars: bool,
+    pub(crate) sandbox: Option<bool>,
+    pub(crate) lang: Option<String>,
+    pub(crate) extra_args: Vec<String>,
+    pub(crate) removed_args: Vec<String>,
     pub(crate) temp_dir: CustomTempDir,
-    pub(crate) executable_path: PathBuf,
+    pub(crate) executable_path: Option<PathBuf>,
+    pub(crate) envs: Vec<(OsString, OsString)>,
+    pub(crate) timeouts: Timeouts,
+    pub(crate) keepalive_interval: Option<Duration>,
+    pub(crate) max_message_size: usize,
+    pub(crate) discovery_timeout: Duration,
 }
 
 impl BrowserConfig {
@@ -80,26 +92,95 @@ impl BrowserConfig {
 
         Ok(Self {
             headless: true,
-            executable_path: default_executable()?,
+            headless_fallback: false,
+            hide_scrollbars: true,
+            sandbox: None,
+            lang: None,
+            extra_args: Vec::new(),
+            removed_args: Vec::new(),
+            executable_path: None,
+            envs: Vec::new(),
+            timeouts: Timeouts::default(),
+            keepalive_interval: None,
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            discovery_timeout: Duration::from_secs(30),
             debug_port: get_available_port().context("Failed to get available port")?,
             temp_dir: CustomTempDir::new(temp_dir, "cdp-html-shot")
                 .context("Failed to create custom temporary directory")?,
         })
     }
 
+    /// The configured executable, or an auto-detected one if none was set.
+    pub(crate) fn executable(&self) -> Result<PathBuf> {
+        match &self.executable_path {
+            Some(path) => Ok(path.clone()),
+            None => default_executable(),
+        }
+    }
+
     pub(crate) fn get_browser_args(&self) -> Vec<String> {
         let mut args = vec![
             format!("--remote-debugging-port={}", self.debug_port),
             format!("--user-data-dir={}", self.temp_dir.path().display()),
         ];
 
-        args.extend(DEFAULT_ARGS.iter().map(|s| s.to_string()));
+        args.extend(DEFAULT_ARGS
+            .iter()
+            .filter(|arg| !self.is_removed(arg))
+            .map(|s| s.to_string()));
+        if !self.use_sandbox() {
+            // Chrome refuses to start without a zygote while the sandbox is enabled.
+            args.push("--no-sandbox".to_string());
+            if !self.is_removed("--no-zygote") {
+                args.push("--no-zygote".to_string());
+            }
+        }
         if self.headless {
             args.push("--headless".to_string());
         }
+        if self.hide_scrollbars {
+            args.push("--hide-scrollbars".to_string());
+        }
+        if let Some(lang) = &self.lang {
+            let primary = lang.split(',').next().unwrap_or(lang).trim();
+            args.push(format!("--lang={primary}"));
+            args.push(format!("--accept-lang={lang}"));
+        }
+        args.extend(self.extra_args.iter().cloned());
 
         args
     }
+
+    /// Whether a default argument was removed, by its switch name with or without a value.
+    fn is_removed(&self, arg: &str) -> bool {
+        let name = arg.split('=').next().unwrap_or(arg);
+        self.removed_args.iter().any(|removed| removed == arg || removed == name)
+    }
+
+    /// Whether to keep Chrome's sandbox enabled.
+    ///
+    /// Unless set explicitly, the sandbox is kept for non-root users of a desktop session.
+    /// Chrome refuses to run sandboxed as root (e.g. in Docker), and on Linux and other
+    /// Unix systems no `DISPLAY` or `WAYLAND_DISPLAY` usually means a container without
+    /// the required privileges. macOS and Windows are always treated as desktops.
+    pub(crate) fn use_sandbox(&self) -> bool {
+        self.sandbox.unwrap_or_else(sandbox_supported)
+    }
+}
+
+#[cfg(unix)]
+fn sandbox_supported() -> bool {
+    let is_root = unsafe { libc::geteuid() } == 0;
+    let has_desktop = cfg!(target_os = "macos")
+        || std::env::var_os("DISPLAY").is_some()
+        || std::env::var_os("WAYLAND_DISPLAY").is_some();
+
+    !is_root && has_desktop
+}
+
+#[cfg(not(unix))]
+fn sandbox_supported() -> bool {
+    true
 }
 
 fn default_executable() -> Result<PathBuf> {
@@ -188,4 +269,31 @@ fn get_available_port() -> Option<u16> {
 
 fn port_is_available(port: u16) -> bool {
     net::TcpListener::bind(("127.0.0.1", port)).is_ok()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args_with_sandbox(sandbox: bool) -> Vec<String> {
+        let mut config = BrowserConfig::new().unwrap();
+        config.sandbox = Some(sandbox);
+        config.get_browser_args()
+    }
+
+    #[test]
+    fn sandboxed_args_keep_the_zygote() {
+        let args = args_with_sandbox(true);
+
+        assert!(!args.iter().any(|arg| arg == "--no-sandbox"));
+        assert!(!args.iter().any(|arg| arg == "--no-zygote"));
+    }
+
+    #[test]
+    fn unsandboxed_args_disable_the_zygote() {
+        let args = args_with_sandbox(false);
+
+        assert!(args.iter().any(|arg| arg == "--no-sandbox"));
+        assert!(args.iter().any(|arg| arg == "--no-zygote"));
+    }
+}
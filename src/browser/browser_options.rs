@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::transport::Timeouts;
+
+/**
+Options for launching a browser with [`Browser::new_with_options`].
+
+Construct with struct update syntax so that options added later don't break your code.
+
+[`Browser::new_with_options`]: crate::Browser::new_with_options
+
+# Example
+```no_run
+use cdp_html_shot::{Browser, BrowserOptions};
+use anyhow::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let browser = Browser::new_with_options(BrowserOptions {
+        headless: false,
+        args: vec!["--window-size=1280,720".to_string()],
+        ..Default::default()
+    }).await?;
+    Ok(())
+}
+```
+*/
+#[derive(Debug, Clone)]
+pub struct BrowserOptions {
+    /// Whether to run without a visible window. Defaults to `true`.
+    pub headless: bool,
+    /// The Chrome executable to launch, auto-detected when `None`.
+    pub executable_path: Option<PathBuf>,
+    /// Extra command line arguments, appended after the default ones.
+    pub args: Vec<String>,
+    /// Whether to keep Chrome's sandbox enabled, decided automatically when `None`.
+    pub sandbox: Option<bool>,
+    /// The browser language and default `Accept-Language`, e.g. `"de-DE"`.
+    pub lang: Option<String>,
+    /// How long to wait for the reply to a single command. Defaults to 10 seconds.
+    pub command_timeout: Duration,
+    /// How long to wait for a page to load. Defaults to 60 seconds.
+    pub navigation_timeout: Duration,
+}
+
+impl Default for BrowserOptions {
+    fn default() -> Self {
+        let timeouts = Timeouts::default();
+
+        Self {
+            headless: true,
+            executable_path: None,
+            args: Vec::new(),
+            sandbox: None,
+            lang: None,
+            command_timeout: timeouts.command,
+            navigation_timeout: timeouts.navigation,
+        }
+    }
+}
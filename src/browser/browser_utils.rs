@@ -1,18 +1,30 @@
 use regex::Regex;
-use anyhow::{Context, Result};
+use log::debug;
+use tokio::time;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use serde_json::Value;
+use tokio::net::TcpStream;
+use anyhow::{anyhow, bail, Context, Result};
 use std::io::{BufRead, BufReader};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use std::process::{ChildStderr, Command, Stdio};
 
 use crate::browser::browser_config::BrowserConfig;
 
-pub(crate) fn spawn_chrome_process(config: &BrowserConfig) -> Result<std::process::Child> {
-    let mut command = Command::new(&config.executable_path);
+pub(crate) fn spawn_chrome_process(
+    config: &BrowserConfig,
+    executable_path: &Path,
+) -> Result<std::process::Child> {
+    let mut command = Command::new(executable_path);
 
     #[cfg(windows)]
     configure_windows_process(&mut command);
 
     command
         .args(config.get_browser_args())
+        .envs(config.envs.iter().map(|(key, value)| (key, value)))
         .stderr(Stdio::piped())
         .spawn()
         .context("Failed to spawn a Chrome process")
@@ -25,14 +37,66 @@ fn configure_windows_process(command: &mut Command) {
     command.creation_flags(CREATE_NO_WINDOW);
 }
 
-pub(crate) async fn get_websocket_url(stderr: ChildStderr) -> Result<String> {
+/// The error of a Chrome process that exited before reporting its DevTools WebSocket URL.
+#[derive(Debug)]
+pub(crate) struct ExitedDuringStartup;
+
+impl std::fmt::Display for ExitedDuringStartup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Chrome exited before reporting its DevTools WebSocket URL")
+    }
+}
+
+impl std::error::Error for ExitedDuringStartup {}
+
+/// Discover the DevTools WebSocket URL of a freshly spawned Chrome, giving up after `timeout`.
+///
+/// Chrome's stderr is parsed for the URL while the `DevToolsActivePort` file in
+/// `user_data_dir` is polled, as some Chrome, Edge and Chromium builds log differently.
+/// Whichever yields the URL first wins. Fails right away if stderr reaches EOF without
+/// the URL, as Chrome has then exited. On timeout, the blocking stderr read only ends
+/// once the process is killed.
+pub(crate) async fn get_websocket_url(
+    stderr: ChildStderr,
+    user_data_dir: &Path,
+    timeout: Duration,
+) -> Result<String> {
     let reader = BufReader::new(stderr);
-    ws_url_from_reader(reader)
-        .await?
-        .context("Failed to get ws url")
+    let mut read = tokio::task::spawn_blocking(move || ws_url_from_reader(reader));
+    let mut stderr_done = false;
+    let active_port = user_data_dir.join("DevToolsActivePort");
+
+    let discover = async {
+        let mut poll = time::interval(Duration::from_millis(100));
+        loop {
+            tokio::select! {
+                res = &mut read, if !stderr_done => {
+                    stderr_done = true;
+                    match res {
+                        Ok(Ok(Some(ws_url))) => return Ok(ws_url),
+                        Ok(Ok(None)) => return Err(anyhow!(ExitedDuringStartup)),
+                        Ok(Err(e)) => debug!("Failed to read stderr ({e}), waiting for DevToolsActivePort"),
+                        Err(e) => debug!("Failed to read stderr ({e}), waiting for DevToolsActivePort"),
+                    }
+                }
+                _ = poll.tick() => {
+                    let ws_url = fs::read_to_string(&active_port)
+                        .ok()
+                        .and_then(|contents| parse_devtools_active_port(&contents));
+                    if let Some(ws_url) = ws_url {
+                        return Ok(ws_url);
+                    }
+                }
+            }
+        }
+    };
+
+    time::timeout(timeout, discover)
+        .await
+        .map_err(|_| anyhow!("Timeout while waiting for the ws url"))?
 }
 
-async fn ws_url_from_reader(reader: BufReader<ChildStderr>) -> Result<Option<String>>
+fn ws_url_from_reader(reader: BufReader<ChildStderr>) -> Result<Option<String>>
 {
     let re = Regex::new(r"listening on (.*/devtools/browser/.*)$")?;
 
@@ -48,4 +112,107 @@ async fn ws_url_from_reader(reader: BufReader<ChildStderr>) -> Result<Option<Str
         }
     }
     Ok(None)
-}
\ No newline at end of file
+}
+
+/// Read a DevTools WebSocket URL from a file holding the URL, a `DevToolsActivePort`
+/// file, or a user data directory containing one.
+pub(crate) fn ws_url_from_file(path: &Path) -> Result<String> {
+    let path = if path.is_dir() {
+        path.join("DevToolsActivePort")
+    } else {
+        path.to_path_buf()
+    };
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let contents = contents.trim();
+
+    if contents.starts_with("ws://") || contents.starts_with("wss://") {
+        return Ok(contents.to_string());
+    }
+
+    parse_devtools_active_port(contents)
+        .with_context(|| format!("No ws url in {}", path.display()))
+}
+
+/// Query the `/json/version` endpoint of a DevTools HTTP server such as `http://chrome:9222`
+/// for the browser's WebSocket URL, giving up after `timeout`.
+///
+/// Chrome rejects requests whose `Host` is neither an IP address nor `localhost`, and reports
+/// its own listening address in the URL, so the request claims `localhost` and the returned
+/// URL is rewritten to the host and port of `base`.
+pub(crate) async fn ws_url_from_http(base: &str, timeout: Duration) -> Result<String> {
+    let Some(rest) = base.strip_prefix("http://") else {
+        bail!("Unsupported DevTools endpoint {}, expected an http:// URL", base);
+    };
+    let authority = rest.split('/').next().unwrap_or_default();
+
+    let body = time::timeout(timeout, get_json_version(authority))
+        .await
+        .map_err(|_| anyhow!("Timed out querying {}", base))?
+        .with_context(|| format!("Failed to query {}/json/version", base.trim_end_matches('/')))?;
+
+    let version: Value = serde_json::from_str(&body).context("Malformed /json/version response")?;
+    let ws_url = version["webSocketDebuggerUrl"]
+        .as_str()
+        .context("No webSocketDebuggerUrl in /json/version response")?;
+    let path = ws_url
+        .find("/devtools/")
+        .map(|start| &ws_url[start..])
+        .with_context(|| format!("Unexpected WebSocket URL {}", ws_url))?;
+
+    Ok(format!("ws://{authority}{path}"))
+}
+
+/// Send `GET /json/version` to `authority` and return the response body.
+///
+/// This is not a general HTTP client: there is no TLS, and the body is read up to its
+/// `Content-Length` or the end of the connection, so chunked responses aren't decoded.
+async fn get_json_version(authority: &str) -> Result<String> {
+    let mut stream = TcpStream::connect(authority).await?;
+    stream.write_all(b"GET /json/version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n").await?;
+
+    // Read until the body is complete, as the server may keep the connection open.
+    let mut response = Vec::new();
+    let mut chunk = [0; 4096];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        response.extend_from_slice(&chunk[..read]);
+
+        let text = String::from_utf8_lossy(&response);
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let content_length = head
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, value)| value.trim().parse::<usize>().ok());
+
+            if read == 0 || content_length.is_some_and(|length| body.len() >= length) {
+                if head.split_whitespace().nth(1) != Some("200") {
+                    bail!("Unexpected response: {}", head.lines().next().unwrap_or_default());
+                }
+                return Ok(body.to_string());
+            }
+        }
+
+        if read == 0 {
+            bail!("Connection closed before a complete response");
+        }
+    }
+}
+
+/// Parse the contents of Chrome's `DevToolsActivePort` file, i.e. the port and the
+/// browser target path on separate lines, into a local WebSocket URL.
+///
+/// Returns `None` for a partially written file.
+pub(crate) fn parse_devtools_active_port(contents: &str) -> Option<String> {
+    let mut lines = contents.lines();
+    let port = lines.next()?.trim().parse::<u16>().ok()?;
+    let path = lines.next()?.trim();
+
+    if !path.starts_with("/devtools/browser/") {
+        return None;
+    }
+
+    Some(format!("ws://127.0.0.1:{port}{path}"))
+}
@@ -0,0 +1,21 @@
+use std::time::Duration;
+
+/// Timings of the browser launch phases, measured from the previous checkpoint.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LaunchMetrics {
+    /// Time to spawn the Chrome process.
+    pub spawn: Duration,
+    /// Time until the DevTools WebSocket URL was discovered.
+    pub ws_discovery: Duration,
+    /// Time to connect the WebSocket.
+    pub ws_connect: Duration,
+    /// Time until the first command (`Browser.getVersion`) succeeded.
+    pub first_command: Duration,
+}
+
+impl LaunchMetrics {
+    /// Total time from spawning the process to the first successful command.
+    pub fn total(&self) -> Duration {
+        self.spawn + self.ws_discovery + self.ws_connect + self.first_command
+    }
+}
@@ -1,4 +1,5 @@
 use std::fs;
+use std::time::Duration;
 use chrono::Local;
 use rand::{thread_rng, Rng};
 use std::path::{Path, PathBuf};
@@ -38,6 +39,12 @@ impl CustomTempDir {
         &self.path
     }
 
+    /// Keep the directory on disk after this value is dropped.
+    pub(crate) fn persist(mut self) -> PathBuf {
+        self.is_cleaned = true;
+        self.path.clone()
+    }
+
     pub(crate) fn cleanup(&mut self) -> Result<()> {
         if self.is_cleaned {
             return Ok(());
@@ -51,6 +58,47 @@ impl CustomTempDir {
     }
 }
 
+/// Remove directories in `base_path` whose name starts with one of `prefixes` followed
+/// by `_` and which were last modified more than `older_than` ago.
+///
+/// Returns the number of directories removed.
+pub(crate) fn remove_stale(base_path: &Path, prefixes: &[&str], older_than: Duration) -> Result<usize> {
+    let entries = match fs::read_dir(base_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context("Failed to read temporary base directory"),
+    };
+
+    let mut removed = 0;
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+
+        let matches = prefixes.iter().any(|prefix| {
+            name.strip_prefix(prefix).is_some_and(|rest| rest.starts_with('_'))
+        });
+        if !matches || !entry.file_type()?.is_dir() {
+            continue;
+        }
+
+        let age = entry
+            .metadata()?
+            .modified()?
+            .elapsed()
+            .unwrap_or_default();
+        if age < older_than {
+            continue;
+        }
+
+        fs::remove_dir_all(entry.path())
+            .with_context(|| format!("Failed to remove {}", entry.path().display()))?;
+        removed += 1;
+    }
+
+    Ok(removed)
+}
+
 fn generate_unique_name(prefix: &str) -> String {
     let timestamp = Local::now().format("%Y%m%d_%H%M%S");
     let random: String = thread_rng()
@@ -1,7 +1,268 @@
+use std::path::Path;
+use anyhow::{anyhow, bail, Result};
+
+use crate::image_utils;
+use crate::watermark::Watermark;
+
+/// The compression quality used for JPEG and WebP captures unless set otherwise.
+pub const DEFAULT_JPEG_QUALITY: u8 = 90;
+
+/// Image formats supported by `Page.captureScreenshot`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFormat {
+    /// PNG when a transparent background is requested, JPEG otherwise.
+    #[default]
+    Auto,
+    Jpeg,
+    Png,
+    Webp,
+}
+
+/// A concrete image format, i.e. an [`ImageFormat`] with `Auto` resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CaptureFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl CaptureFormat {
+    /// The format name expected by the CDP `format` parameter.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            CaptureFormat::Jpeg => "jpeg",
+            CaptureFormat::Png => "png",
+            CaptureFormat::Webp => "webp",
+        }
+    }
+}
+
+impl ImageFormat {
+    /// Resolve `Auto` to PNG when the background is omitted and to JPEG otherwise.
+    pub(crate) fn resolve(self, omit_background: bool) -> CaptureFormat {
+        match self {
+            ImageFormat::Auto if omit_background => CaptureFormat::Png,
+            ImageFormat::Auto => CaptureFormat::Jpeg,
+            ImageFormat::Jpeg => CaptureFormat::Jpeg,
+            ImageFormat::Png => CaptureFormat::Png,
+            ImageFormat::Webp => CaptureFormat::Webp,
+        }
+    }
+
+    /// Parse a file extension such as `"png"` or `".JPG"`, case-insensitively.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        let extension = extension.strip_prefix('.').unwrap_or(extension);
+
+        match extension.to_ascii_lowercase().as_str() {
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "png" => Some(ImageFormat::Png),
+            "webp" => Some(ImageFormat::Webp),
+            _ => None,
+        }
+    }
+
+    /// Parse a MIME type such as `"image/jpeg"`, ignoring case and parameters, so a
+    /// `Content-Type` or `Accept` header value can be passed as is.
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let essence = mime.split(';').next().unwrap_or(mime).trim();
+
+        match essence.to_ascii_lowercase().as_str() {
+            "image/jpeg" | "image/jpg" => Some(ImageFormat::Jpeg),
+            "image/png" => Some(ImageFormat::Png),
+            "image/webp" => Some(ImageFormat::Webp),
+            _ => None,
+        }
+    }
+
+    /// The MIME type of the format, or `None` for `Auto`.
+    pub fn mime_type(&self) -> Option<&'static str> {
+        match self {
+            ImageFormat::Auto => None,
+            ImageFormat::Jpeg => Some("image/jpeg"),
+            ImageFormat::Png => Some("image/png"),
+            ImageFormat::Webp => Some("image/webp"),
+        }
+    }
+
+    /// Infer the image format from the extension of a file path.
+    pub(crate) fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .ok_or_else(|| anyhow!("Cannot infer image format from path: {}", path.display()))?;
+
+        Self::from_extension(extension)
+            .ok_or_else(|| anyhow!("Unsupported image extension: {}", extension))
+    }
+}
+
+/// What an element capture shows behind the element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ElementBackground {
+    /// The element as it appears on the page, over whatever its ancestors paint.
+    /// Only the default white page background is removed by `with_omit_background`.
+    #[default]
+    Page,
+    /// The element alone over transparency: the backgrounds of its ancestors, e.g. of
+    /// `body`, are cleared during the capture. The element's own background is kept.
+    Transparent,
+}
+
+/// Units of the coordinates of a [`ClipRegion`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipUnits {
+    /// CSS pixels, as used by the page layout and expected by CDP.
+    #[default]
+    Css,
+    /// Device pixels, i.e. CSS pixels multiplied by the device pixel ratio,
+    /// as found in an already captured image.
+    Device,
+}
+
+/// How the scale of a [`ClipRegion`] combines with the device pixel ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClipScale {
+    /// The output is `scale * device pixel ratio` image pixels per CSS pixel, as in CDP.
+    /// A scale of 2 on a viewport with a device scale factor of 2 gives 4x output.
+    #[default]
+    Multiply,
+    /// The output is `scale` image pixels per CSS pixel, whatever the device pixel ratio.
+    Replace,
+}
+
+/// A rectangular region of the page to capture.
+///
+/// Create one with [`ClipRegion::new`] or [`ClipRegion::from_points`] and the `with_*`
+/// methods, so fields can be added without breaking callers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct ClipRegion {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Scale applied to the captured region.
+    pub scale: f64,
+    /// Units of `x`, `y`, `width` and `height`.
+    pub units: ClipUnits,
+    /// How `scale` combines with the device pixel ratio.
+    pub scale_mode: ClipScale,
+}
+
+impl ClipRegion {
+    /// Create a clip region in CSS pixels with a scale of 1.
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self { x, y, width, height, scale: 1.0, units: ClipUnits::Css, scale_mode: ClipScale::Multiply }
+    }
+
+    /// Create a clip region in CSS pixels spanning two opposite corners, in any order.
+    pub fn from_points(x0: f64, y0: f64, x1: f64, y1: f64) -> Self {
+        Self::new(x0.min(x1), y0.min(y1), (x1 - x0).abs(), (y1 - y0).abs())
+    }
+
+    /// The overlap of this region and `other`, or `None` if they don't overlap.
+    ///
+    /// Coordinates are compared as-is, so both regions should use the same units.
+    /// The result keeps the scale, units and scale mode of `self`.
+    pub fn intersect(&self, other: &ClipRegion) -> Option<ClipRegion> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+
+        if right <= left || bottom <= top {
+            return None;
+        }
+
+        Some(Self { x: left, y: top, width: right - left, height: bottom - top, ..*self })
+    }
+
+    /// The smallest region containing both this region and `other`.
+    ///
+    /// Coordinates are compared as-is, so both regions should use the same units.
+    /// The result keeps the scale, units and scale mode of `self`.
+    pub fn union(&self, other: &ClipRegion) -> ClipRegion {
+        let left = self.x.min(other.x);
+        let top = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Self { x: left, y: top, width: right - left, height: bottom - top, ..*self }
+    }
+
+    /// Whether the point (`x`, `y`) lies within this region, edges included.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        x >= self.x && x <= self.x + self.width && y >= self.y && y <= self.y + self.height
+    }
+
+    /// Set the scale applied to the captured region.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Set how the scale combines with the device pixel ratio.
+    ///
+    /// Use [`ClipScale::Replace`] to get predictable output sizes when a viewport with a
+    /// device scale factor is also set.
+    pub fn with_scale_mode(mut self, scale_mode: ClipScale) -> Self {
+        self.scale_mode = scale_mode;
+        self
+    }
+
+    /// Expand the region outwards to whole output pixels, given the number of output
+    /// pixels per unit of the coordinates.
+    ///
+    /// With fractional device pixel ratios (e.g. 1.25 or 1.5), an unaligned region would
+    /// be rounded inconsistently by Chrome and come out a pixel short or resampled.
+    pub(crate) fn snap_to_pixels(&self, pixels_per_unit: f64) -> Self {
+        // Tolerate floating point noise, so 100.0000001 pixels doesn't become 101.
+        const EPSILON: f64 = 1e-6;
+
+        let left = (self.x * pixels_per_unit + EPSILON).floor();
+        let top = (self.y * pixels_per_unit + EPSILON).floor();
+        let right = ((self.x + self.width) * pixels_per_unit - EPSILON).ceil();
+        let bottom = ((self.y + self.height) * pixels_per_unit - EPSILON).ceil();
+
+        Self {
+            x: left / pixels_per_unit,
+            y: top / pixels_per_unit,
+            width: (right - left).max(1.0) / pixels_per_unit,
+            height: (bottom - top).max(1.0) / pixels_per_unit,
+            ..*self
+        }
+    }
+
+    /// Set the units of the coordinates.
+    ///
+    /// With [`ClipUnits::Device`] on a HiDPI page (device pixel ratio > 1),
+    /// coordinates are divided by the device pixel ratio before capture.
+    pub fn with_units(mut self, units: ClipUnits) -> Self {
+        self.units = units;
+        self
+    }
+}
+
 /// Configuration options for HTML capture.
 #[derive(Debug, Clone, Default)]
 pub struct CaptureOptions {
-    pub(crate) raw_png: bool,
+    pub(crate) format: ImageFormat,
+    pub(crate) quality: Option<u8>,
+    pub(crate) omit_background: bool,
+    pub(crate) clip: Option<ClipRegion>,
+    pub(crate) clip_selector: Option<String>,
+    pub(crate) frame_id: Option<String>,
+    pub(crate) wait_for_images: Option<u64>,
+    pub(crate) network_idle: Option<u64>,
+    pub(crate) ready_script: Option<String>,
+    pub(crate) webp_lossless: bool,
+    pub(crate) from_surface: Option<bool>,
+    pub(crate) empty_retries: Option<u32>,
+    pub(crate) capture_beyond_viewport: Option<bool>,
+    pub(crate) hide_scrollbars: Option<bool>,
+    pub(crate) element_background: ElementBackground,
+    pub(crate) watermark: Option<Watermark>,
+    pub(crate) full_page: bool,
 }
 
 impl CaptureOptions {
@@ -12,7 +273,412 @@ impl CaptureOptions {
 
     /// Set whether to use a raw PNG format (true) or JPEG (false).
     pub fn with_raw_png(mut self, raw: bool) -> Self {
-        self.raw_png = raw;
+        self.format = if raw { ImageFormat::Png } else { ImageFormat::Jpeg };
+        self
+    }
+
+    /// Set the image format.
+    ///
+    /// Defaults to [`ImageFormat::Auto`], which captures PNG when the background is
+    /// omitted and JPEG otherwise. `Element::screenshot_to_file` instead picks the
+    /// format from the file extension when the format is left as `Auto`.
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set whether to produce lossless WebP, ignoring the format and quality options.
+    ///
+    /// CDP has no lossless WebP mode, so the capture is taken as PNG and re-encoded.
+    /// Requires the `image` feature; without it, captures fail with an error before
+    /// anything is captured.
+    pub fn with_webp_lossless(mut self, lossless: bool) -> Self {
+        self.webp_lossless = lossless;
+        self
+    }
+
+    /// The concrete format to request from `Page.captureScreenshot`.
+    pub(crate) fn capture_format(&self) -> CaptureFormat {
+        if self.webp_lossless || self.watermark.is_some() {
+            CaptureFormat::Png
+        } else {
+            self.output_format()
+        }
+    }
+
+    /// The format of the finished capture, ignoring lossless WebP.
+    fn output_format(&self) -> CaptureFormat {
+        self.format.resolve(self.omit_background || self.element_background == ElementBackground::Transparent)
+    }
+
+    /// Resolve the options for writing a capture to `path`: an `Auto` format is inferred
+    /// from the extension, and lossless WebP must be written to a `.webp` file.
+    pub(crate) fn for_path(mut self, path: &Path) -> Result<Self> {
+        if self.webp_lossless {
+            if ImageFormat::from_path(path)? != ImageFormat::Webp {
+                bail!("Lossless WebP captures can't be written to {}, expected a .webp file", path.display());
+            }
+        } else if self.format == ImageFormat::Auto {
+            self.format = ImageFormat::from_path(path)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Fail if post-processing is requested that this build can't do, so the error comes
+    /// before a capture that would be thrown away.
+    pub(crate) fn check_supported(&self) -> Result<()> {
+        if cfg!(not(feature = "image")) {
+            if self.webp_lossless {
+                bail!("Lossless WebP requires the `image` feature");
+            }
+            if self.watermark.is_some() {
+                bail!("Watermarks require the `image` feature");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Post-process image data captured in `capture_format`.
+    pub(crate) fn finish_capture(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if let Some(watermark) = &self.watermark {
+            let format = if self.webp_lossless { CaptureFormat::Webp } else { self.output_format() };
+            image_utils::apply_watermark(&data, watermark, format, self.quality())
+        } else if self.webp_lossless {
+            image_utils::png_to_lossless_webp(&data)
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// Composite `watermark` onto captures.
+    ///
+    /// The capture is taken as PNG and encoded into the requested format once the
+    /// watermark is applied, so JPEG isn't compressed twice; WebP comes out lossless.
+    /// Requires the `image` feature; without it, captures fail with an error before
+    /// anything is captured.
+    pub fn with_watermark(mut self, watermark: Watermark) -> Self {
+        self.watermark = Some(watermark);
+        self
+    }
+
+    /// Set the compression quality (0-100) for JPEG and WebP captures.
+    ///
+    /// Defaults to [`DEFAULT_JPEG_QUALITY`].
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    /// The compression quality to use for JPEG and WebP captures.
+    pub(crate) fn quality(&self) -> u8 {
+        self.quality.unwrap_or(DEFAULT_JPEG_QUALITY)
+    }
+
+    /// Set whether to hide the default white page background, allowing transparent captures.
+    ///
+    /// Only areas the page itself leaves unpainted become transparent: a background set
+    /// on `body` or an ancestor of a captured element still shows. Use
+    /// `with_element_background` to clear those too. Transparency is only preserved by
+    /// PNG and WebP.
+    pub fn with_omit_background(mut self, omit: bool) -> Self {
+        self.omit_background = omit;
+        self
+    }
+
+    /// Set what element captures show behind the element. Defaults to
+    /// [`ElementBackground::Page`].
+    ///
+    /// [`ElementBackground::Transparent`] implies `with_omit_background(true)`, and makes
+    /// [`ImageFormat::Auto`] resolve to PNG. It has no effect on `Tab::screenshot`.
+    pub fn with_element_background(mut self, background: ElementBackground) -> Self {
+        self.element_background = background;
+        self
+    }
+
+    /// Set whether to capture from the compositor surface (default) rather than the
+    /// cached main-frame content.
+    ///
+    /// Try `false` if captures come out blank in some headful or GPU configurations.
+    pub fn with_from_surface(mut self, from_surface: bool) -> Self {
+        self.from_surface = Some(from_surface);
+        self
+    }
+
+    /// Set whether to capture content beyond the visible viewport.
+    ///
+    /// When unset, element captures always do, and `Tab::screenshot` only does when its
+    /// clip extends past the viewport. Only has a visible effect with a clip reaching
+    /// past the viewport.
+    /// Takes precedence over `Tab::set_capture_beyond_viewport`.
+    pub fn with_capture_beyond_viewport(mut self, enabled: bool) -> Self {
+        self.capture_beyond_viewport = Some(enabled);
+        self
+    }
+
+    /// Show or hide scrollbars in the tab used by `Browser::capture_html_with_options` or
+    /// `Browser::capture_html_named`, overriding `BrowserBuilder::hide_scrollbars`.
+    pub fn with_hide_scrollbars(mut self, hide: bool) -> Self {
+        self.hide_scrollbars = Some(hide);
         self
     }
-}
\ No newline at end of file
+
+    /// Set how many times to retry a capture that comes back without image data.
+    ///
+    /// Defaults to 2. Such captures occasionally happen on busy machines using software
+    /// rendering; each retry waits a little longer before re-issuing the capture.
+    pub fn with_empty_capture_retries(mut self, retries: u32) -> Self {
+        self.empty_retries = Some(retries);
+        self
+    }
+
+    /// Retries for captures without image data.
+    pub(crate) fn empty_retries(&self) -> u32 {
+        self.empty_retries.unwrap_or(2)
+    }
+
+    /// Wait until no network requests have been in flight for `idle_ms` after the content
+    /// is loaded by `Browser::capture_html_with_options`, `Browser::capture_html_named` or
+    /// `Browser::capture_url_to_file`, up to the navigation timeout.
+    ///
+    /// Lets lazy images, fonts and late API calls finish before the capture. Runs before
+    /// `with_wait_for_images`.
+    pub fn with_wait_for_network_idle(mut self, idle_ms: u64) -> Self {
+        self.network_idle = Some(idle_ms);
+        self
+    }
+
+    /// Wait up to `timeout_ms` for all images to decode after the content is loaded
+    /// by `Browser::capture_html_with_options`, `Browser::capture_html_named` or
+    /// `Browser::capture_url_to_file`.
+    pub fn with_wait_for_images(mut self, timeout_ms: u64) -> Self {
+        self.wait_for_images = Some(timeout_ms);
+        self
+    }
+
+    /// Evaluate `script` after the content is loaded by `Browser::capture_html_with_options`,
+    /// `Browser::capture_html_named` or `Browser::capture_url_to_file`, and capture once
+    /// it settles.
+    ///
+    /// A returned promise is awaited, e.g. `window.__APP_READY__`, up to the navigation
+    /// timeout. A thrown exception or rejection fails the capture. Runs after
+    /// `with_wait_for_images`.
+    pub fn with_ready_script(mut self, script: impl Into<String>) -> Self {
+        self.ready_script = Some(script.into());
+        self
+    }
+
+    /// Set whether `Tab::screenshot` captures the whole document rather than the viewport.
+    ///
+    /// The clip is set to the document's full content size and content beyond the
+    /// viewport is rendered, so tall pages aren't cut off. Takes precedence over
+    /// `with_clip` and `with_clip_from_selector`, though the scale of a `with_clip`
+    /// region is kept. A viewport set with `Tab::set_viewport` is restored afterwards.
+    /// Element captures ignore this option.
+    pub fn with_full_page(mut self, full_page: bool) -> Self {
+        self.full_page = full_page;
+        self
+    }
+
+    /// Set the region to capture with `Tab::screenshot`.
+    ///
+    /// Element captures always clip to the element and ignore this option.
+    pub fn with_clip(mut self, clip: ClipRegion) -> Self {
+        self.clip = Some(clip);
+        self.clip_selector = None;
+        self
+    }
+
+    /// Interpret the clip set with `with_clip` relative to the frame with id `frame_id`,
+    /// as listed by `Tab::frames`, instead of the main frame.
+    ///
+    /// The clip is offset by the position of the frame's content box, so it lands on the
+    /// same region of the composited page.
+    pub fn with_frame(mut self, frame_id: impl Into<String>) -> Self {
+        self.frame_id = Some(frame_id.into());
+        self
+    }
+
+    /// Set the region to capture with `Tab::screenshot` to the border box of the first
+    /// element matching `selector`, resolved at capture time.
+    ///
+    /// Replaces a clip set with `with_clip`.
+    pub fn with_clip_from_selector(mut self, selector: impl Into<String>) -> Self {
+        self.clip_selector = Some(selector.into());
+        self.clip = None;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assert that `snapped` has whole-pixel edges at `ratio` and covers `original`.
+    fn assert_snapped(original: ClipRegion, snapped: ClipRegion, ratio: f64) {
+        let edges = [
+            snapped.x * ratio,
+            snapped.y * ratio,
+            (snapped.x + snapped.width) * ratio,
+            (snapped.y + snapped.height) * ratio,
+        ];
+        for edge in edges {
+            assert!((edge - edge.round()).abs() < 1e-9, "edge {edge} is not a whole pixel at {ratio}");
+        }
+
+        assert!(snapped.x <= original.x && snapped.y <= original.y);
+        assert!(snapped.x + snapped.width >= original.x + original.width - 1e-9);
+        assert!(snapped.y + snapped.height >= original.y + original.height - 1e-9);
+    }
+
+    #[test]
+    fn snap_to_pixels_at_fractional_ratios() {
+        let clip = ClipRegion::new(10.3, 20.7, 100.5, 50.2);
+
+        for ratio in [1.25, 1.5, 1.75] {
+            assert_snapped(clip, clip.snap_to_pixels(ratio), ratio);
+        }
+    }
+
+    #[test]
+    fn snap_to_pixels_expands_outwards() {
+        let snapped = ClipRegion::new(10.3, 20.7, 100.5, 50.2).snap_to_pixels(1.25);
+
+        assert_eq!((snapped.x * 1.25).round(), 12.0);
+        assert_eq!((snapped.y * 1.25).round(), 25.0);
+        assert_eq!(((snapped.x + snapped.width) * 1.25).round(), 139.0);
+        assert_eq!(((snapped.y + snapped.height) * 1.25).round(), 89.0);
+    }
+
+    #[test]
+    fn snap_to_pixels_keeps_aligned_regions() {
+        // All edges are whole pixels at each ratio.
+        let clip = ClipRegion::new(4.0, 8.0, 100.0, 48.0);
+
+        for ratio in [1.25, 1.5, 1.75] {
+            let snapped = clip.snap_to_pixels(ratio);
+            assert!((snapped.x - clip.x).abs() < 1e-9);
+            assert!((snapped.y - clip.y).abs() < 1e-9);
+            assert!((snapped.width - clip.width).abs() < 1e-9);
+            assert!((snapped.height - clip.height).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn snap_to_pixels_keeps_at_least_one_pixel() {
+        let snapped = ClipRegion::new(5.0, 5.0, 0.0, 0.0).snap_to_pixels(1.5);
+
+        assert!((snapped.width * 1.5 - 1.0).abs() < 1e-9);
+        assert!((snapped.height * 1.5 - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn from_points_normalizes_reversed_points() {
+        let expected = ClipRegion::new(10.0, 20.0, 30.0, 40.0);
+
+        assert_eq!(ClipRegion::from_points(10.0, 20.0, 40.0, 60.0), expected);
+        assert_eq!(ClipRegion::from_points(40.0, 60.0, 10.0, 20.0), expected);
+        assert_eq!(ClipRegion::from_points(40.0, 20.0, 10.0, 60.0), expected);
+    }
+
+    #[test]
+    fn intersect_overlapping() {
+        let a = ClipRegion::new(0.0, 0.0, 100.0, 100.0);
+        let b = ClipRegion::new(50.0, 25.0, 100.0, 100.0);
+
+        assert_eq!(a.intersect(&b), Some(ClipRegion::new(50.0, 25.0, 50.0, 75.0)));
+        assert_eq!(b.intersect(&a), Some(ClipRegion::new(50.0, 25.0, 50.0, 75.0)));
+    }
+
+    #[test]
+    fn intersect_disjoint_is_none() {
+        let a = ClipRegion::new(0.0, 0.0, 10.0, 10.0);
+
+        assert_eq!(a.intersect(&ClipRegion::new(20.0, 0.0, 10.0, 10.0)), None);
+        assert_eq!(a.intersect(&ClipRegion::new(0.0, 20.0, 10.0, 10.0)), None);
+    }
+
+    #[test]
+    fn intersect_empty_is_none() {
+        let a = ClipRegion::new(0.0, 0.0, 10.0, 10.0);
+
+        // Touching edges and zero-sized regions have no area in common.
+        assert_eq!(a.intersect(&ClipRegion::new(10.0, 0.0, 10.0, 10.0)), None);
+        assert_eq!(a.intersect(&ClipRegion::new(5.0, 5.0, 0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn intersect_keeps_scale_of_self() {
+        let a = ClipRegion::new(0.0, 0.0, 10.0, 10.0).with_scale(2.0);
+        let b = ClipRegion::new(5.0, 5.0, 10.0, 10.0);
+
+        assert_eq!(a.intersect(&b).unwrap().scale, 2.0);
+    }
+
+    #[test]
+    fn union_spans_both() {
+        let a = ClipRegion::new(0.0, 0.0, 10.0, 10.0);
+        let b = ClipRegion::new(20.0, 5.0, 10.0, 10.0);
+
+        assert_eq!(a.union(&b), ClipRegion::new(0.0, 0.0, 30.0, 15.0));
+    }
+
+    #[test]
+    fn contains_includes_edges() {
+        let clip = ClipRegion::from_points(10.0, 10.0, 0.0, 0.0);
+
+        assert!(clip.contains(0.0, 0.0));
+        assert!(clip.contains(10.0, 10.0));
+        assert!(clip.contains(5.0, 5.0));
+        assert!(!clip.contains(10.1, 5.0));
+        assert!(!clip.contains(5.0, -0.1));
+    }
+
+    #[test]
+    fn image_format_from_extension() {
+        assert_eq!(ImageFormat::from_extension("jpg"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_extension(".JPEG"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_extension("png"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_extension("WebP"), Some(ImageFormat::Webp));
+        assert_eq!(ImageFormat::from_extension("gif"), None);
+        assert_eq!(ImageFormat::from_extension(""), None);
+    }
+
+    #[test]
+    fn image_format_from_mime() {
+        assert_eq!(ImageFormat::from_mime("image/jpeg"), Some(ImageFormat::Jpeg));
+        assert_eq!(ImageFormat::from_mime("IMAGE/PNG"), Some(ImageFormat::Png));
+        assert_eq!(ImageFormat::from_mime("image/webp; q=0.8"), Some(ImageFormat::Webp));
+        assert_eq!(ImageFormat::from_mime("image/svg+xml"), None);
+        assert_eq!(ImageFormat::from_mime("text/html"), None);
+    }
+
+    #[test]
+    fn for_path_infers_an_auto_format() {
+        let options = CaptureOptions::new().for_path(Path::new("out/shot.PNG")).unwrap();
+        assert_eq!(options.format, ImageFormat::Png);
+
+        let options = CaptureOptions::new().with_format(ImageFormat::Jpeg);
+        assert_eq!(options.for_path(Path::new("shot.png")).unwrap().format, ImageFormat::Jpeg);
+
+        assert!(CaptureOptions::new().for_path(Path::new("shot.gif")).is_err());
+        assert!(CaptureOptions::new().for_path(Path::new("shot")).is_err());
+    }
+
+    #[test]
+    fn for_path_rejects_lossless_webp_in_other_files() {
+        let lossless = CaptureOptions::new().with_webp_lossless(true);
+
+        assert!(lossless.clone().for_path(Path::new("shot.webp")).is_ok());
+        assert!(lossless.clone().for_path(Path::new("shot.png")).is_err());
+        assert!(lossless.for_path(Path::new("shot.jpg")).is_err());
+    }
+
+    #[test]
+    fn check_supported_matches_the_image_feature() {
+        let lossless = CaptureOptions::new().with_webp_lossless(true);
+        assert_eq!(lossless.check_supported().is_ok(), cfg!(feature = "image"));
+        assert!(CaptureOptions::new().check_supported().is_ok());
+    }
+}
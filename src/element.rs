@@ -1,15 +1,23 @@
+use std::hash::{Hash, Hasher};
+use tokio::time;
+use std::path::Path;
+use time::{Duration, Instant};
 use serde_json::json;
 use anyhow::{Context, Result};
 
 use crate::tab::Tab;
 use crate::general_utils;
-use crate::general_utils::next_id;
+use crate::capture_options::{CaptureOptions, ClipRegion, ElementBackground, DEFAULT_JPEG_QUALITY};
 
 /// Represents screenshot configuration parameters.
 #[derive(Debug)]
 struct ScreenshotConfig {
     format: &'static str,
     quality: Option<u8>,
+    omit_background: bool,
+    from_surface: bool,
+    empty_retries: u32,
+    capture_beyond_viewport: Option<bool>,
 }
 
 impl Default for ScreenshotConfig {
@@ -17,10 +25,70 @@ impl Default for ScreenshotConfig {
         Self {
             format: "png",
             quality: None,
+            omit_background: false,
+            from_surface: true,
+            empty_retries: 2,
+            capture_beyond_viewport: None,
         }
     }
 }
 
+/// Make the backgrounds of an element's ancestors transparent, remembering their inline styles.
+const CLEAR_ANCESTOR_BACKGROUNDS: &str = r#"function() {
+    const saved = [];
+    for (let el = this.parentElement; el; el = el.parentElement) {
+        saved.push([el, el.style.getPropertyValue('background'), el.style.getPropertyPriority('background')]);
+        el.style.setProperty('background', 'transparent', 'important');
+    }
+    this.__cdpHtmlShotBackgrounds = saved;
+}"#;
+
+/// Undo `CLEAR_ANCESTOR_BACKGROUNDS`.
+const RESTORE_ANCESTOR_BACKGROUNDS: &str = r#"function() {
+    for (const [el, value, priority] of this.__cdpHtmlShotBackgrounds || []) {
+        if (value) {
+            el.style.setProperty('background', value, priority);
+        } else {
+            el.style.removeProperty('background');
+        }
+    }
+    delete this.__cdpHtmlShotBackgrounds;
+}"#;
+
+/// The boxes of an element as reported by `DOM.getBoxModel`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoxModel {
+    pub content: ClipRegion,
+    pub padding: ClipRegion,
+    pub border: ClipRegion,
+    pub margin: ClipRegion,
+    /// Width of the element's node.
+    pub width: f64,
+    /// Height of the element's node.
+    pub height: f64,
+}
+
+/// Convert a CDP quad (four x/y points) to the rectangle enclosing it.
+fn quad_to_rect(quad: &serde_json::Value) -> Option<ClipRegion> {
+    let points = quad.as_array()?
+        .iter()
+        .map(|v| v.as_f64())
+        .collect::<Option<Vec<f64>>>()?;
+
+    if points.len() != 8 {
+        return None;
+    }
+
+    let xs = points.iter().step_by(2);
+    let ys = points.iter().skip(1).step_by(2);
+    let left = xs.clone().cloned().fold(f64::INFINITY, f64::min);
+    let right = xs.cloned().fold(f64::NEG_INFINITY, f64::max);
+    let top = ys.clone().cloned().fold(f64::INFINITY, f64::min);
+    let bottom = ys.cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    Some(ClipRegion::new(left, top, right - left, bottom - top))
+}
+
 /// An element instance.
 pub struct Element<'a> {
     parent: &'a Tab,
@@ -32,9 +100,26 @@ pub struct Element<'a> {
     // remote_object_id: String,
 }
 
+/// Two handles are equal when they refer to the same DOM node of the same tab.
+impl PartialEq for Element<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.backend_node_id == other.backend_node_id
+            && self.parent.session_id == other.parent.session_id
+    }
+}
+
+impl Eq for Element<'_> {}
+
+impl Hash for Element<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.backend_node_id.hash(state);
+        self.parent.session_id.hash(state);
+    }
+}
+
 impl<'a> Element<'a> {
     pub(crate) async fn new(parent: &'a Tab, node_id: u64) -> Result<Self> {
-        let msg_id = next_id();
+        let msg_id = parent.transport.next_id();
         let msg = json!({
             "id": msg_id,
             "method": "DOM.describeNode",
@@ -44,7 +129,13 @@ impl<'a> Element<'a> {
             }
         }).to_string();
 
-        let res = general_utils::send_and_get_msg(parent.transport.clone(), msg_id, &parent.session_id, msg).await?;
+        let res = general_utils::send_and_get_msg_within(
+            parent.transport.clone(),
+            msg_id,
+            &parent.session_id,
+            msg,
+            parent.timeouts().command,
+        ).await?;
 
         let msg = general_utils::serde_msg(&res);
 
@@ -72,7 +163,7 @@ impl<'a> Element<'a> {
             .as_u64()
             .context("Failed to convert backendNodeId to u64")?;
 
-        // let msg_id = next_id();
+        // let msg_id = parent.transport.next_id();
         // let msg = json!({
         //     "id": msg_id,
         //     "method": "DOM.resolveNode",
@@ -111,92 +202,357 @@ impl<'a> Element<'a> {
         })
     }
 
-    /// Get the box model dimensions for an element.
-    async fn get_box_model_dimensions(&self) -> Result<(f64, f64, f64, f64)> {
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "DOM.getBoxModel",
-            "params": {
-                "backendNodeId": self.backend_node_id
-            }
-        }).to_string();
+    /**
+    Get the full box model of the element, with each box in CSS pixels relative to the document.
 
-        let res = general_utils::send_and_get_msg(
-            self.parent.transport.clone(),
-            msg_id,
-            &self.parent.session_id,
-            msg
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div style='padding: 8px; margin: 16px'>Hi</div>").await?;
+
+        let element = tab.find_element("div").await?;
+        let model = element.box_model().await?;
+        println!("content: {:?}, margin: {:?}", model.content, model.margin);
+        Ok(())
+    }
+    ```
+    */
+    pub async fn box_model(&self) -> Result<BoxModel> {
+        let res = self.parent.send_cmd("DOM.getBoxModel", json!({
+            "backendNodeId": self.backend_node_id
+        })).await?;
+
+        let model = res.get("model").context("Failed to get model")?;
+
+        Ok(BoxModel {
+            content: quad_to_rect(&model["content"]).context("Failed to get content quad")?,
+            padding: quad_to_rect(&model["padding"]).context("Failed to get padding quad")?,
+            border: quad_to_rect(&model["border"]).context("Failed to get border quad")?,
+            margin: quad_to_rect(&model["margin"]).context("Failed to get margin quad")?,
+            width: model["width"].as_f64().context("Failed to get width")?,
+            height: model["height"].as_f64().context("Failed to get height")?,
+        })
+    }
+
+    /**
+    Get the element's `getBoundingClientRect()` in CSS pixels, relative to the viewport.
+
+    Unlike [`bounding_box`], the coordinates change as the page scrolls, which is what
+    clip math needs when capturing the visible viewport without `captureBeyondViewport`.
+
+    [`bounding_box`]: struct.Element.html#method.bounding_box
+    */
+    pub async fn client_rect(&self) -> Result<ClipRegion> {
+        let rect = self.call_function(
+            "function() { const r = this.getBoundingClientRect(); return { x: r.x, y: r.y, width: r.width, height: r.height }; }"
         ).await?;
 
-        let msg = general_utils::serde_msg(&res);
-        let model = msg["result"]
-            .get("model")
-            .context("Failed to get model")?;
-
-        Ok((
-            model["border"][0].as_f64().unwrap(), // top_left_x
-            model["border"][1].as_f64().unwrap(), // top_left_y
-            model["border"][2].as_f64().unwrap(), // top_right_x
-            model["border"][5].as_f64().unwrap()  // bottom_left_y
+        Ok(ClipRegion::new(
+            rect["x"].as_f64().context("Failed to get x")?,
+            rect["y"].as_f64().context("Failed to get y")?,
+            rect["width"].as_f64().context("Failed to get width")?,
+            rect["height"].as_f64().context("Failed to get height")?,
         ))
     }
 
+    /// The backend node id, stable for the lifetime of the node within its tab.
+    pub fn backend_node_id(&self) -> u64 {
+        self.backend_node_id
+    }
+
+    /**
+    Resolve the element to a JavaScript remote object id for `Runtime` calls.
+
+    Each call creates a new remote object, so ids of the same node differ; compare
+    elements with `==` instead.
+    */
+    pub async fn remote_object_id(&self) -> Result<String> {
+        let res = self.parent.send_cmd("DOM.resolveNode", json!({
+            "backendNodeId": self.backend_node_id
+        })).await?;
+
+        let object_id = res["object"]["objectId"]
+            .as_str()
+            .context("Failed to get objectId")?
+            .to_string();
+
+        Ok(object_id)
+    }
+
+    /// Call a JavaScript function with the element as `this` and return its value.
+    async fn call_function(&self, function_declaration: &str) -> Result<serde_json::Value> {
+        let object_id = self.remote_object_id().await?;
+
+        let res = self.parent.send_cmd("Runtime.callFunctionOn", json!({
+            "functionDeclaration": function_declaration,
+            "objectId": object_id,
+            "returnByValue": true,
+            "awaitPromise": true
+        })).await?;
+
+        if let Some(exception) = res.get("exceptionDetails") {
+            return Err(anyhow::anyhow!("Function call failed: {}", exception["exception"]["description"]
+                .as_str()
+                .or(exception["text"].as_str())
+                .unwrap_or("unknown error")));
+        }
+
+        Ok(res["result"]["value"].clone())
+    }
+
+    /**
+    Get the element's `outerHTML`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let element = tab.find_element("h1").await?;
+        assert_eq!(element.outer_html().await?, "<h1>Hello world!</h1>");
+        Ok(())
+    }
+    ```
+    */
+    pub async fn outer_html(&self) -> Result<String> {
+        let res = self.parent.send_cmd("DOM.getOuterHTML", json!({
+            "backendNodeId": self.backend_node_id
+        })).await?;
+
+        let html = res["outerHTML"]
+            .as_str()
+            .context("Failed to get outerHTML")?
+            .to_string();
+
+        Ok(html)
+    }
+
+    /**
+    Get the classes of the element, in document order.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div class='card is-active'></div>").await?;
+
+        let element = tab.find_element("div").await?;
+        assert_eq!(element.class_list().await?, ["card", "is-active"]);
+        Ok(())
+    }
+    ```
+    */
+    pub async fn class_list(&self) -> Result<Vec<String>> {
+        let classes = self.call_function("function() { return Array.from(this.classList ?? []); }").await?;
+
+        serde_json::from_value(classes).context("Failed to parse class list")
+    }
+
+    /// Whether the element has the class `name`, e.g. to capture it only in an error state.
+    pub async fn has_class(&self, name: &str) -> Result<bool> {
+        let function = format!(
+            "function() {{ return this.classList?.contains({}) ?? false; }}",
+            serde_json::to_string(name)?,
+        );
+
+        Ok(self.call_function(&function).await?.as_bool().unwrap_or(false))
+    }
+
+    /**
+    Get the current value of an `<input>`, `<textarea>` or `<select>` element.
+    */
+    pub async fn value(&self) -> Result<String> {
+        let value = self.call_function("function() { return String(this.value ?? ''); }").await?;
+
+        Ok(value.as_str().unwrap_or_default().to_string())
+    }
+
+    /**
+    Set the value of an `<input>`, `<textarea>` or `<select>` element and dispatch
+    `input` and `change` events.
+
+    The value is set through the native property setter, so frameworks that track
+    values themselves, like React and Vue, pick up the change.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<input name='email'>").await?;
+
+        let input = tab.find_element("input").await?;
+        input.set_value("user@example.com").await?;
+        assert_eq!(input.value().await?, "user@example.com");
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_value(&self, value: &str) -> Result<&Self> {
+        let function = format!(
+            r#"function() {{
+                const setter = Object.getOwnPropertyDescriptor(Object.getPrototypeOf(this), 'value')?.set;
+                if (setter) {{ setter.call(this, {value}); }} else {{ this.value = {value}; }}
+                this.dispatchEvent(new Event('input', {{ bubbles: true }}));
+                this.dispatchEvent(new Event('change', {{ bubbles: true }}));
+            }}"#,
+            value = serde_json::to_string(value)?,
+        );
+
+        self.call_function(&function).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Scroll the element into view if it is not already visible.
+
+    Returns an error if the element is no longer attached to the document.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div style='height: 3000px'></div><footer>End</footer>").await?;
+
+        let element = tab.find_element("footer").await?;
+        element.scroll_into_view().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn scroll_into_view(&self) -> Result<&Self> {
+        self.parent.send_cmd("DOM.scrollIntoViewIfNeeded", json!({
+            "backendNodeId": self.backend_node_id
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Get the border box of the element in CSS pixels, relative to the document.
+
+    This is stable while scrolling and is what element captures clip to. For coordinates
+    relative to the current viewport, use [`client_rect`].
+
+    [`client_rect`]: struct.Element.html#method.client_rect
+    */
+    pub async fn bounding_box(&self) -> Result<ClipRegion> {
+        Ok(self.box_model().await?.border)
+    }
+
+    /**
+    Wait until the element's bounding box stops changing, e.g. after fonts or async
+    content make it reflow.
+
+    Samples the box every `interval_ms` and returns once it is unchanged across `checks`
+    consecutive samples. Gives up with an error after the navigation timeout.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div id='chart'></div>").await?;
+
+        let element = tab.find_element("#chart").await?;
+        element.wait_until_stable(3, 100).await?.screenshot().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn wait_until_stable(&self, checks: u32, interval_ms: u64) -> Result<&Self> {
+        let deadline = Instant::now() + self.parent.timeouts().navigation;
+        let mut last = self.bounding_box().await?;
+        let mut stable = 0;
+
+        while stable < checks {
+            if Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Timeout while waiting for the element to stop moving"));
+            }
+
+            time::sleep(Duration::from_millis(interval_ms)).await;
+
+            let current = self.bounding_box().await?;
+            if current == last {
+                stable += 1;
+            } else {
+                stable = 0;
+                last = current;
+            }
+        }
+
+        Ok(self)
+    }
+
     /// Take a screenshot with the given configuration.
     async fn take_screenshot_with_config(&self, config: ScreenshotConfig) -> Result<String> {
-        let (top_left_x, top_left_y, top_right_x, bottom_left_y) =
-            self.get_box_model_dimensions().await?;
+        let device_pixel_ratio = self.parent.device_pixel_ratio().await?;
+        let border = self.box_model().await?.border.snap_to_pixels(device_pixel_ratio);
 
         let mut params = json!({
             "format": config.format,
             "clip": {
-                "x": top_left_x,
-                "y": top_left_y,
-                "width": top_right_x - top_left_x,
-                "height": bottom_left_y - top_left_y,
+                "x": border.x,
+                "y": border.y,
+                "width": border.width,
+                "height": border.height,
                 "scale": 1.0
             },
-            "fromSurface": true,
+            "fromSurface": config.from_surface,
             "captureBeyondViewport": true,
         });
 
-        if config.format == "jpeg" {
+        if config.format != "png" {
             if let Some(quality) = config.quality {
                 params["quality"] = json!(quality);
             }
         }
 
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "Page.captureScreenshot",
-            "params": params
-        }).to_string();
-
-        self.parent.activate().await?;
-        let res = general_utils::send_and_get_msg(
-            self.parent.transport.clone(),
-            msg_id,
-            &self.parent.session_id,
-            msg
-        ).await?;
-
-        let msg = general_utils::serde_msg(&res);
-        let base64 = msg["result"]
-            .get("data")
-            .context("Failed to get data")?
-            .as_str()
-            .context("Failed to convert data to string")?
-            .to_string();
-
-        Ok(base64)
+        self.parent.capture_screenshot(
+            params,
+            config.omit_background,
+            config.capture_beyond_viewport,
+            config.empty_retries,
+        ).await
     }
 
-    /// Capture a screenshot of the element in JPEG format.
+    /// Capture a screenshot of the element in JPEG format at [`DEFAULT_JPEG_QUALITY`].
     pub async fn screenshot(&self) -> Result<String> {
         self.take_screenshot_with_config(ScreenshotConfig {
             format: "jpeg",
-            quality: Some(90),
+            quality: Some(DEFAULT_JPEG_QUALITY),
+            ..Default::default()
         }).await
     }
 
@@ -204,4 +560,171 @@ impl<'a> Element<'a> {
     pub async fn raw_screenshot(&self) -> Result<String> {
         self.take_screenshot_with_config(ScreenshotConfig::default()).await
     }
+
+    /// Capture a screenshot of the element with the given options.
+    ///
+    /// Returns the base64-encoded image data. With the default [`ImageFormat::Auto`],
+    /// PNG is used when the background is omitted and JPEG otherwise.
+    ///
+    /// With [`ElementBackground::Transparent`], the backgrounds of the element's ancestors
+    /// are cleared for the duration of the capture and restored afterwards.
+    ///
+    /// [`ImageFormat::Auto`]: crate::ImageFormat::Auto
+    pub async fn screenshot_with_options(&self, options: CaptureOptions) -> Result<String> {
+        let data = self.screenshot_bytes(options).await?;
+
+        Ok(general_utils::encode_base64(&data))
+    }
+
+    /**
+    Capture a screenshot of the element with the given options and return the decoded
+    image bytes, ready to write to a file or send over the network.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let element = tab.find_element("h1").await?;
+        let png = element.screenshot_bytes(CaptureOptions::new().with_raw_png(true)).await?;
+        std::fs::write("hello.png", png)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_bytes(&self, options: CaptureOptions) -> Result<Vec<u8>> {
+        options.check_supported()?;
+        let format = options.capture_format();
+        let transparent = options.element_background == ElementBackground::Transparent;
+
+        if transparent {
+            self.call_function(CLEAR_ANCESTOR_BACKGROUNDS).await?;
+        }
+
+        let res = self.take_screenshot_with_config(ScreenshotConfig {
+            format: format.as_str(),
+            quality: Some(options.quality()),
+            omit_background: options.omit_background || transparent,
+            from_surface: options.from_surface.unwrap_or(true),
+            empty_retries: options.empty_retries(),
+            capture_beyond_viewport: options.capture_beyond_viewport,
+        }).await;
+
+        if transparent {
+            self.call_function(RESTORE_ANCESTOR_BACKGROUNDS).await?;
+        }
+
+        options.finish_capture(general_utils::decode_capture(&res?)?)
+    }
+
+    /**
+    Capture a screenshot of the element with `options` and write the image to `path`.
+
+    The same as [`screenshot_to_file`]: the format is inferred from the extension unless
+    set in `options`, and missing parent directories are created.
+
+    [`screenshot_to_file`]: struct.Element.html#method.screenshot_to_file
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let element = tab.find_element("h1").await?;
+        element.save_to_file("cache/hello.jpeg", CaptureOptions::new()).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn save_to_file(&self, path: impl AsRef<Path>, options: CaptureOptions) -> Result<()> {
+        self.screenshot_to_file(path, options).await
+    }
+
+    /**
+    Capture a part of the element, given in CSS pixels relative to its border box.
+
+    The region is clipped to the element, so it may extend past the element's edges,
+    but it must overlap it. Other options apply as in `Tab::screenshot`; a clip set in
+    `options` is replaced.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<table><tr><th>Name</th></tr><tr><td>Rust</td></tr></table>").await?;
+
+        let table = tab.find_element("table").await?;
+        let header = table.screenshot_sub_region(0.0, 0.0, 200.0, 24.0, CaptureOptions::new()).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_sub_region(
+        &self,
+        offset_x: f64,
+        offset_y: f64,
+        width: f64,
+        height: f64,
+        options: CaptureOptions,
+    ) -> Result<String> {
+        let border = self.bounding_box().await?;
+        let region = ClipRegion::new(border.x + offset_x, border.y + offset_y, width, height);
+
+        let clip = border
+            .intersect(&region)
+            .context("The sub-region does not overlap the element")?;
+
+        self.parent.screenshot(options.with_clip(clip)).await
+    }
+
+    /**
+    Capture a screenshot of the element and write the decoded image to `path`, creating
+    missing parent directories.
+
+    If `options` leaves the format as `Auto`, it is inferred from the file extension
+    (`.jpg`/`.jpeg`, `.png` or `.webp`), and an unsupported extension is an error.
+    Lossless WebP can only be written to a `.webp` file.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let element = tab.find_element("h1").await?;
+        element.screenshot_to_file("hello.png", CaptureOptions::new()).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_to_file(&self, path: impl AsRef<Path>, options: CaptureOptions) -> Result<()> {
+        let path = path.as_ref();
+        let options = options.for_path(path)?;
+
+        let img_data = self.screenshot_bytes(options).await?;
+
+        general_utils::write_creating_dirs(path, &img_data)
+    }
 }
\ No newline at end of file
@@ -1,15 +1,80 @@
+use std::fs;
 use std::sync::Arc;
-use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::time::Duration;
+use std::collections::HashMap;
+use base64::Engine;
+use anyhow::{anyhow, Context, Result};
 use serde_json::{json, Value};
-use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::transport::Transport;
 use crate::transport_actor::{TargetMessage, TransportResponse};
 
-pub(crate) static GLOBAL_ID_COUNTER: AtomicUsize = AtomicUsize::new(0);
+pub(crate) fn decode_base64(data: &str) -> Result<Vec<u8>> {
+    base64::prelude::BASE64_STANDARD
+        .decode(data)
+        .context("Failed to decode base64 data")
+}
+
+/// Decode the base64 image data of a capture.
+pub(crate) fn decode_capture(data: &str) -> Result<Vec<u8>> {
+    decode_base64(data).context("The browser sent malformed base64 image data")
+}
+
+/// Write `data` to `path`, creating missing parent directories first.
+pub(crate) fn write_creating_dirs(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
 
-pub(crate) fn next_id() -> usize {
-    GLOBAL_ID_COUNTER.fetch_add(1, Ordering::SeqCst) + 1
+    fs::write(path, data)
+        .with_context(|| format!("Failed to write screenshot to {}", path.display()))
+}
+
+pub(crate) fn encode_base64(data: &[u8]) -> String {
+    base64::prelude::BASE64_STANDARD.encode(data)
+}
+
+/// Replace each `{{key}}` placeholder in `template` with the HTML-escaped value of `key`.
+///
+/// Whitespace around the key is ignored. A placeholder without a value is an error, so
+/// typos don't silently render as literal braces.
+pub(crate) fn render_template(template: &str, data: &HashMap<&str, &str>) -> Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .map(|end| start + end)
+            .ok_or_else(|| anyhow!("Unclosed placeholder in template"))?;
+        let key = rest[start + 2..end].trim();
+        let value = data
+            .get(key)
+            .ok_or_else(|| anyhow!("No value for template placeholder: {}", key))?;
+
+        out.push_str(&rest[..start]);
+        escape_html(value, &mut out);
+        rest = &rest[end + 2..];
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Append `text` to `out` with the characters significant in HTML escaped.
+pub(crate) fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
 }
 
 pub(crate) fn serde_msg(msg: &TargetMessage) -> Value {
@@ -17,26 +82,84 @@ pub(crate) fn serde_msg(msg: &TargetMessage) -> Value {
     message
 }
 
-pub(crate) async fn send_and_get_msg(
+/// Send a CDP command to a session and return its result, or an error for an error reply.
+pub(crate) async fn send_cmd(
+    transport: Arc<Transport>,
+    session_id: &str,
+    method: &str,
+    params: Value,
+    timeout: Duration,
+) -> Result<Value> {
+    let msg_id = transport.next_id();
+    let msg = json!({
+        "id": msg_id,
+        "method": method,
+        "params": params
+    }).to_string();
+
+    let res = send_and_get_msg_within(transport, msg_id, session_id, msg, timeout)
+        .await
+        .map_err(|e| anyhow!("{} failed: {}", method, e))?;
+
+    Ok(serde_msg(&res)["result"].clone())
+}
+
+/// Send a raw message to a session and wait up to `timeout` for the reply.
+pub(crate) async fn send_and_get_msg_within(
     transport: Arc<Transport>,
     msg_id: usize,
     session_id: &str,
     msg: String,
+    timeout: Duration,
 ) -> Result<TargetMessage> {
     let (_, target_msg) = futures::try_join!(
         transport.send(json!({
-            "id": next_id(),
+            "id": transport.next_id(),
             "method": "Target.sendMessageToTarget",
             "params": {
                 "sessionId": session_id,
                 "message": msg
             }
         })),
-        transport.get_target_msg(msg_id),
+        transport.get_target_msg(msg_id, timeout),
     )?;
 
     match target_msg {
         TransportResponse::Target(res) => Ok(res),
         other => Err(anyhow!("Unexpected transport response: {:?}", other)),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_template_escapes_values() {
+        let data = HashMap::from([("name", "<Tom & \"Jerry\">"), ("score", "42")]);
+        let html = render_template("<p>{{ name }}: {{score}}</p>", &data).unwrap();
+
+        assert_eq!(html, "<p>&lt;Tom &amp; &quot;Jerry&quot;&gt;: 42</p>");
+    }
+
+    #[test]
+    fn render_template_leaves_text_without_placeholders() {
+        let html = render_template("<p>{ not a placeholder }</p>", &HashMap::new()).unwrap();
+
+        assert_eq!(html, "<p>{ not a placeholder }</p>");
+    }
+
+    #[test]
+    fn render_template_rejects_missing_values() {
+        let err = render_template("<p>{{name}}</p>", &HashMap::new()).unwrap_err();
+
+        assert!(err.to_string().contains("name"));
+    }
+
+    #[test]
+    fn render_template_rejects_unclosed_placeholders() {
+        let data = HashMap::from([("name", "Tom")]);
+
+        assert!(render_template("<p>{{name</p>", &data).is_err());
+    }
+}
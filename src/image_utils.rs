@@ -0,0 +1,298 @@
+use anyhow::Result;
+use sha2::{Digest, Sha256};
+
+use crate::general_utils;
+
+use crate::capture_options::CaptureFormat;
+use crate::watermark::Watermark;
+
+#[cfg(feature = "image")]
+use {
+    std::io::Cursor,
+    anyhow::Context,
+    image::{DynamicImage, GenericImageView},
+    image::codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+};
+
+/// Re-encode PNG data as lossless WebP.
+#[cfg(feature = "image")]
+pub(crate) fn png_to_lossless_webp(png: &[u8]) -> Result<Vec<u8>> {
+    let img = image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .context("Failed to decode PNG capture")?;
+
+    let mut webp = Cursor::new(Vec::new());
+    img.write_with_encoder(WebPEncoder::new_lossless(&mut webp))
+        .context("Failed to encode lossless WebP")?;
+
+    Ok(webp.into_inner())
+}
+
+#[cfg(not(feature = "image"))]
+pub(crate) fn png_to_lossless_webp(_png: &[u8]) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!("Lossless WebP requires the `image` feature"))
+}
+
+/// Crop borders within `tolerance` of the top-left pixel's color from PNG data, and
+/// encode the result as `format`.
+#[cfg(feature = "image")]
+pub(crate) fn trim_uniform_border(png: &[u8], tolerance: u8, format: CaptureFormat, quality: u8) -> Result<Vec<u8>> {
+    let img = image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .context("Failed to decode PNG capture")?;
+
+    let (width, height) = img.dimensions();
+    let background = img.get_pixel(0, 0).0;
+    let is_background = |x: u32, y: u32| {
+        img.get_pixel(x, y).0
+            .iter()
+            .zip(background.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+    };
+    let row_is_background = |y: u32| (0..width).all(|x| is_background(x, y));
+    let column_is_background = |x: u32, top: u32, bottom: u32| (top..bottom).all(|y| is_background(x, y));
+
+    let Some(top) = (0..height).find(|&y| !row_is_background(y)) else {
+        // Nothing but background: keep a single pixel rather than an empty image.
+        return encode(&img.crop_imm(0, 0, 1, 1), format, quality);
+    };
+    let bottom = (top..height).rev().find(|&y| !row_is_background(y)).unwrap_or(top) + 1;
+    let left = (0..width).find(|&x| !column_is_background(x, top, bottom)).unwrap_or(0);
+    let right = (left..width).rev().find(|&x| !column_is_background(x, top, bottom)).unwrap_or(left) + 1;
+
+    encode(&img.crop_imm(left, top, right - left, bottom - top), format, quality)
+}
+
+#[cfg(not(feature = "image"))]
+pub(crate) fn trim_uniform_border(_png: &[u8], _tolerance: u8, _format: CaptureFormat, _quality: u8) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!("Trimming captures requires the `image` feature"))
+}
+
+/// Composite `watermark` onto PNG data, and encode the result as `format`.
+#[cfg(feature = "image")]
+pub(crate) fn apply_watermark(png: &[u8], watermark: &Watermark, format: CaptureFormat, quality: u8) -> Result<Vec<u8>> {
+    use crate::watermark::WatermarkPosition;
+
+    let mut img = image::load_from_memory_with_format(png, image::ImageFormat::Png)
+        .context("Failed to decode PNG capture")?
+        .to_rgba8();
+
+    let mut mark = image::load_from_memory(&watermark.image)
+        .context("Failed to decode watermark image")?
+        .to_rgba8();
+    for pixel in mark.pixels_mut() {
+        pixel[3] = (f32::from(pixel[3]) * watermark.opacity).round() as u8;
+    }
+
+    let (width, height) = (i64::from(img.width()), i64::from(img.height()));
+    let (mark_width, mark_height) = (i64::from(mark.width()), i64::from(mark.height()));
+    let margin = i64::from(watermark.margin);
+
+    let (x, y) = match watermark.position {
+        WatermarkPosition::TopLeft => (margin, margin),
+        WatermarkPosition::TopRight => (width - mark_width - margin, margin),
+        WatermarkPosition::BottomLeft => (margin, height - mark_height - margin),
+        WatermarkPosition::BottomRight => (width - mark_width - margin, height - mark_height - margin),
+        WatermarkPosition::Center => ((width - mark_width) / 2, (height - mark_height) / 2),
+    };
+    image::imageops::overlay(&mut img, &mark, x, y);
+
+    encode(&DynamicImage::ImageRgba8(img), format, quality)
+}
+
+#[cfg(not(feature = "image"))]
+pub(crate) fn apply_watermark(_png: &[u8], _watermark: &Watermark, _format: CaptureFormat, _quality: u8) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!("Watermarks require the `image` feature"))
+}
+
+/// Encode an image as `format`; WebP is always lossless.
+#[cfg(feature = "image")]
+fn encode(img: &DynamicImage, format: CaptureFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut data = Cursor::new(Vec::new());
+
+    let res = match format {
+        CaptureFormat::Jpeg => img.to_rgb8().write_with_encoder(JpegEncoder::new_with_quality(&mut data, quality)),
+        CaptureFormat::Webp => img.write_with_encoder(WebPEncoder::new_lossless(&mut data)),
+        CaptureFormat::Png => img.write_with_encoder(PngEncoder::new(&mut data)),
+    };
+    res.context("Failed to encode image")?;
+
+    Ok(data.into_inner())
+}
+
+/**
+Hex-encoded SHA-256 of a base64 capture's decoded image bytes.
+
+Identical bytes give identical hashes, so this is a cheap way to tell whether a capture
+changed without keeping the previous image around. Any pixel difference changes the hash;
+see `perceptual_hash` for a hash tolerant of antialiasing noise.
+
+# Example
+```no_run
+use cdp_html_shot::{content_hash, Browser};
+use anyhow::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let browser = Browser::new().await?;
+    let base64 = browser.capture_html("<h1>Hello world!</h1>", "h1").await?;
+    println!("{}", content_hash(&base64)?);
+    Ok(())
+}
+```
+*/
+pub fn content_hash(base64: &str) -> Result<String> {
+    let bytes = general_utils::decode_base64(base64)?;
+    let digest = Sha256::digest(&bytes);
+
+    Ok(digest.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/**
+64-bit difference hash (dHash) of a base64 capture.
+
+Visually similar images give hashes that differ in few bits, so minor antialiasing or
+compression differences can be tolerated by comparing with `hash_distance` against a
+small threshold (e.g. 5) instead of requiring equality.
+
+Requires the `image` feature.
+*/
+#[cfg(feature = "image")]
+pub fn perceptual_hash(base64: &str) -> Result<u64> {
+    let bytes = general_utils::decode_base64(base64)?;
+    let img = image::load_from_memory(&bytes)
+        .context("Failed to decode capture")?
+        .grayscale()
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if img.get_pixel(x, y)[0] < img.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Number of differing bits between two `perceptual_hash` values.
+#[cfg(feature = "image")]
+pub fn hash_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// The outcome of comparing a capture with a baseline image.
+#[cfg(feature = "image")]
+#[derive(Debug, Clone)]
+pub struct DiffResult {
+    /// Fraction of pixels (0.0-1.0) that differ by more than the tolerance.
+    pub diff_fraction: f64,
+    /// PNG image of the capture, faded, with differing pixels highlighted in red.
+    pub diff_image: Vec<u8>,
+}
+
+#[cfg(feature = "image")]
+impl DiffResult {
+    /// Whether no pixel differs beyond the tolerance.
+    pub fn is_identical(&self) -> bool {
+        self.diff_fraction == 0.0
+    }
+}
+
+/// Compare two encoded images pixel by pixel.
+///
+/// A pixel differs when any channel differs by more than `tolerance` (0.0-1.0) of the
+/// full channel range. If the sizes differ, pixels outside either image count as differing.
+#[cfg(feature = "image")]
+pub(crate) fn diff_images(actual: &[u8], baseline: &[u8], tolerance: f64) -> Result<DiffResult> {
+    let actual = image::load_from_memory(actual).context("Failed to decode capture")?.to_rgba8();
+    let baseline = image::load_from_memory(baseline).context("Failed to decode baseline")?.to_rgba8();
+
+    let width = actual.width().max(baseline.width());
+    let height = actual.height().max(baseline.height());
+    let threshold = (tolerance.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    let mut diff = image::RgbaImage::new(width, height);
+    let mut differing = 0u64;
+
+    for y in 0..height {
+        for x in 0..width {
+            let a = actual.get_pixel_checked(x, y);
+            let b = baseline.get_pixel_checked(x, y);
+
+            let differs = match (a, b) {
+                (Some(a), Some(b)) => a.0.iter().zip(b.0.iter()).any(|(a, b)| a.abs_diff(*b) > threshold),
+                _ => true,
+            };
+
+            let pixel = if differs {
+                differing += 1;
+                image::Rgba([255, 0, 0, 255])
+            } else {
+                // Fade matching pixels towards white so the differences stand out.
+                let [r, g, b, _] = a.map_or([255; 4], |pixel| pixel.0);
+                image::Rgba([r / 4 + 191, g / 4 + 191, b / 4 + 191, 255])
+            };
+            diff.put_pixel(x, y, pixel);
+        }
+    }
+
+    let mut diff_image = Cursor::new(Vec::new());
+    diff.write_with_encoder(PngEncoder::new(&mut diff_image))
+        .context("Failed to encode diff image")?;
+
+    let total = u64::from(width) * u64::from(height);
+
+    Ok(DiffResult {
+        diff_fraction: if total == 0 { 0.0 } else { differing as f64 / total as f64 },
+        diff_image: diff_image.into_inner(),
+    })
+}
+
+#[cfg(all(test, feature = "image"))]
+mod tests {
+    use super::*;
+
+    /// Encode a `width` by `height` PNG filled with `rgb`.
+    fn solid_png(width: u32, height: u32, rgb: [u8; 3]) -> Vec<u8> {
+        let [r, g, b] = rgb;
+        let img = image::RgbaImage::from_pixel(width, height, image::Rgba([r, g, b, 255]));
+
+        let mut png = Cursor::new(Vec::new());
+        img.write_with_encoder(PngEncoder::new(&mut png)).unwrap();
+        png.into_inner()
+    }
+
+    #[test]
+    fn diff_identical_images() {
+        let png = solid_png(4, 4, [10, 20, 30]);
+        let diff = diff_images(&png, &png, 0.0).unwrap();
+
+        assert!(diff.is_identical());
+        assert_eq!(diff.diff_fraction, 0.0);
+    }
+
+    #[test]
+    fn diff_different_sizes_counts_missing_pixels() {
+        let actual = solid_png(2, 2, [10, 20, 30]);
+        let baseline = solid_png(2, 1, [10, 20, 30]);
+        let diff = diff_images(&actual, &baseline, 0.0).unwrap();
+
+        assert_eq!(diff.diff_fraction, 0.5);
+        let diff_image = image::load_from_memory(&diff.diff_image).unwrap();
+        assert_eq!(diff_image.dimensions(), (2, 2));
+    }
+
+    #[test]
+    fn diff_tolerance_boundary() {
+        // A tolerance of 0.2 allows channels to differ by up to 51 of 255.
+        let baseline = solid_png(2, 2, [100, 100, 100]);
+        let within = solid_png(2, 2, [151, 100, 100]);
+        let beyond = solid_png(2, 2, [152, 100, 100]);
+
+        assert!(diff_images(&within, &baseline, 0.2).unwrap().is_identical());
+        assert_eq!(diff_images(&beyond, &baseline, 0.2).unwrap().diff_fraction, 1.0);
+    }
+}
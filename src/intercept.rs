@@ -0,0 +1,116 @@
+use std::sync::{Arc, Weak};
+use std::collections::HashMap;
+use log::warn;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::general_utils;
+use crate::transport::Transport;
+use crate::transport_actor::TargetMessage;
+
+/// A request paused by `Tab::set_intercept_requests`.
+#[derive(Debug, Clone)]
+pub struct InterceptedRequest {
+    pub url: String,
+    pub method: String,
+    pub headers: HashMap<String, String>,
+    /// The CDP resource type, e.g. `Document`, `Image` or `XHR`.
+    pub resource_type: String,
+}
+
+/// What to do with an intercepted request.
+#[derive(Debug, Clone)]
+pub enum InterceptAction {
+    /// Let the request continue unchanged.
+    Continue,
+    /// Fail the request as if it was blocked by the client.
+    Fail,
+    /// Answer the request without hitting the network.
+    Fulfill {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    },
+    /// Continue the request with another URL, e.g. a local mock server.
+    ModifyUrl(String),
+}
+
+impl InterceptAction {
+    /// Fulfill the request with a `200` JSON response.
+    pub fn json(body: &Value) -> Self {
+        InterceptAction::Fulfill {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    /// The `Fetch` command and parameters that carry out this action.
+    fn command(self, request_id: &str) -> (&'static str, Value) {
+        match self {
+            InterceptAction::Continue => ("Fetch.continueRequest", json!({ "requestId": request_id })),
+            InterceptAction::Fail => ("Fetch.failRequest", json!({
+                "requestId": request_id,
+                "errorReason": "BlockedByClient"
+            })),
+            InterceptAction::Fulfill { status, headers, body } => ("Fetch.fulfillRequest", json!({
+                "requestId": request_id,
+                "responseCode": status,
+                "responseHeaders": headers
+                    .into_iter()
+                    .map(|(name, value)| json!({ "name": name, "value": value }))
+                    .collect::<Vec<_>>(),
+                "body": general_utils::encode_base64(&body)
+            })),
+            InterceptAction::ModifyUrl(url) => ("Fetch.continueRequest", json!({
+                "requestId": request_id,
+                "url": url
+            })),
+        }
+    }
+}
+
+pub(crate) type InterceptHandler = dyn Fn(&InterceptedRequest) -> InterceptAction + Send + Sync;
+
+/// Answer `Fetch.requestPaused` events with `handler` until the connection closes.
+///
+/// Only a weak reference to the transport is held, so it can still be shut down.
+pub(crate) async fn run_interceptor(
+    transport: Weak<Transport>,
+    session_id: String,
+    mut events: UnboundedReceiver<TargetMessage>,
+    handler: Arc<InterceptHandler>,
+) {
+    while let Some(event) = events.recv().await {
+        let message = general_utils::serde_msg(&event);
+        let params = &message["params"];
+
+        let Some(request_id) = params["requestId"].as_str() else {
+            continue;
+        };
+
+        let request = InterceptedRequest {
+            url: params["request"]["url"].as_str().unwrap_or_default().to_string(),
+            method: params["request"]["method"].as_str().unwrap_or_default().to_string(),
+            headers: params["request"]["headers"]
+                .as_object()
+                .map(|headers| headers
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.as_str().unwrap_or_default().to_string()))
+                    .collect())
+                .unwrap_or_default(),
+            resource_type: params["resourceType"].as_str().unwrap_or_default().to_string(),
+        };
+
+        let (method, params) = handler(&request).command(request_id);
+        let Some(transport) = transport.upgrade() else {
+            break;
+        };
+        let timeout = transport.timeouts.command;
+
+        if let Err(e) = general_utils::send_cmd(transport, &session_id, method, params, timeout).await {
+            warn!("Failed to answer intercepted request {}: {:?}", request.url, e);
+        }
+    }
+}
+
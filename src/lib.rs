@@ -70,18 +70,37 @@ async fn main() -> Result<()> {
 */
 
 mod tab;
+mod types;
 mod browser;
 mod element;
+mod viewport;
 mod transport;
 mod general_utils;
 mod transport_actor;
+mod intercept;
+mod network;
+mod image_utils;
 mod capture_options;
+mod pdf_options;
+mod watermark;
 #[cfg(feature = "atexit")]
 mod exit_hook;
 
+pub mod prelude;
+
 pub use tab::Tab;
-pub use element::Element;
-pub use browser::Browser;
-pub use capture_options::CaptureOptions;
+pub use types::{ConsoleMessage, Cookie, SameSite};
+pub use element::{BoxModel, Element};
+pub use viewport::Viewport;
+pub use pdf_options::PdfOptions;
+pub use browser::{Browser, BrowserBuilder, BrowserOptions, LaunchMetrics};
+pub use image_utils::content_hash;
+pub use intercept::{InterceptAction, InterceptedRequest};
+pub use watermark::{Watermark, WatermarkPosition};
+pub use capture_options::{
+    CaptureOptions, ClipRegion, ClipScale, ClipUnits, ElementBackground, ImageFormat, DEFAULT_JPEG_QUALITY,
+};
+#[cfg(feature = "image")]
+pub use image_utils::{hash_distance, perceptual_hash, DiffResult};
 #[cfg(feature = "atexit")]
 pub use exit_hook::ExitHook;
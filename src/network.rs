@@ -0,0 +1,299 @@
+use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use chrono::DateTime;
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::general_utils;
+use crate::transport_actor::TargetMessage;
+
+/// The `Network` events a recording listens to.
+pub(crate) const RECORDED_EVENTS: [&str; 4] = [
+    "Network.requestWillBeSent",
+    "Network.responseReceived",
+    "Network.loadingFinished",
+    "Network.loadingFailed",
+];
+
+/// One request/response pair of a recording.
+#[derive(Debug, Default)]
+struct Entry {
+    request: Value,
+    wall_time: f64,
+    start: f64,
+    response: Option<Value>,
+    response_time: Option<f64>,
+    end: Option<f64>,
+    encoded_length: Option<f64>,
+    error: Option<String>,
+}
+
+impl Entry {
+    fn to_har(&self) -> Value {
+        let started = DateTime::from_timestamp_millis((self.wall_time * 1000.0) as i64)
+            .map(|time| time.to_rfc3339())
+            .unwrap_or_default();
+
+        let end = self.end.or(self.response_time).unwrap_or(self.start);
+        let wait = self.response_time.map_or(end - self.start, |time| time - self.start);
+        let receive = end - self.start - wait;
+        let response = self.response.as_ref().unwrap_or(&Value::Null);
+
+        let mut entry = json!({
+            "startedDateTime": started,
+            "time": (end - self.start) * 1000.0,
+            "request": {
+                "method": self.request["method"],
+                "url": self.request["url"],
+                "httpVersion": response["protocol"].as_str().unwrap_or("HTTP/1.1"),
+                "headers": har_headers(&self.request["headers"]),
+                "queryString": [],
+                "cookies": [],
+                "headersSize": -1,
+                "bodySize": self.request["postData"].as_str().map_or(0, |data| data.len()),
+            },
+            "response": {
+                "status": response["status"].as_u64().unwrap_or(0),
+                "statusText": response["statusText"].as_str().unwrap_or_default(),
+                "httpVersion": response["protocol"].as_str().unwrap_or("HTTP/1.1"),
+                "headers": har_headers(&response["headers"]),
+                "cookies": [],
+                "content": {
+                    "size": self.encoded_length.unwrap_or(-1.0),
+                    "mimeType": response["mimeType"].as_str().unwrap_or_default(),
+                },
+                "redirectURL": response["headers"]["location"].as_str()
+                    .or(response["headers"]["Location"].as_str())
+                    .unwrap_or_default(),
+                "headersSize": -1,
+                "bodySize": self.encoded_length.unwrap_or(-1.0),
+            },
+            "cache": {},
+            "timings": {
+                "send": 0,
+                "wait": wait * 1000.0,
+                "receive": receive * 1000.0,
+            },
+        });
+
+        if let Some(error) = &self.error {
+            entry["_error"] = json!(error);
+        }
+
+        entry
+    }
+}
+
+/// Convert a CDP headers object to a HAR headers array.
+fn har_headers(headers: &Value) -> Vec<Value> {
+    headers
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| json!({ "name": name, "value": value.as_str().unwrap_or_default() }))
+        .collect()
+}
+
+/// Network events of a tab, collected in request order.
+#[derive(Debug, Default)]
+pub(crate) struct Recording {
+    entries: Vec<Entry>,
+    /// The index of the latest entry of each request id; redirects reuse the id.
+    by_request_id: HashMap<String, usize>,
+}
+
+impl Recording {
+    fn handle(&mut self, method: &str, params: &Value) {
+        let Some(request_id) = params["requestId"].as_str() else {
+            return;
+        };
+        let timestamp = params["timestamp"].as_f64().unwrap_or_default();
+        let index = self.by_request_id.get(request_id).copied();
+
+        match method {
+            "Network.requestWillBeSent" => {
+                if let (Some(index), Some(redirect)) = (index, params.get("redirectResponse")) {
+                    let entry = &mut self.entries[index];
+                    entry.response = Some(redirect.clone());
+                    entry.response_time = Some(timestamp);
+                    entry.end = Some(timestamp);
+                }
+
+                self.by_request_id.insert(request_id.to_string(), self.entries.len());
+                self.entries.push(Entry {
+                    request: params["request"].clone(),
+                    wall_time: params["wallTime"].as_f64().unwrap_or_default(),
+                    start: timestamp,
+                    ..Default::default()
+                });
+            }
+            "Network.responseReceived" => if let Some(index) = index {
+                let entry = &mut self.entries[index];
+                entry.response = Some(params["response"].clone());
+                entry.response_time = Some(timestamp);
+            }
+            "Network.loadingFinished" => if let Some(index) = index {
+                let entry = &mut self.entries[index];
+                entry.end = Some(timestamp);
+                entry.encoded_length = params["encodedDataLength"].as_f64();
+            }
+            "Network.loadingFailed" => if let Some(index) = index {
+                let entry = &mut self.entries[index];
+                entry.end = Some(timestamp);
+                entry.error = Some(params["errorText"].as_str().unwrap_or("failed").to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// The ids of requests that have started but neither finished nor failed.
+    pub(crate) fn in_flight(&self) -> HashSet<String> {
+        self.by_request_id
+            .iter()
+            .filter(|(_, index)| self.entries[**index].end.is_none())
+            .map(|(request_id, _)| request_id.clone())
+            .collect()
+    }
+
+    /// Serialize the recording as a HAR 1.2 log.
+    pub(crate) fn to_har(&self) -> Value {
+        json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "pages": [],
+                "entries": self.entries.iter().map(Entry::to_har).collect::<Vec<_>>(),
+            }
+        })
+    }
+}
+
+/// Add network events to `recording` until all receivers are closed.
+pub(crate) async fn run_recorder(
+    recording: Arc<Mutex<Recording>>,
+    mut receivers: Vec<UnboundedReceiver<TargetMessage>>,
+) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+    for mut receiver in receivers.drain(..) {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    while let Some(event) = rx.recv().await {
+        let message = general_utils::serde_msg(&event);
+        let method = message["method"].as_str().unwrap_or_default();
+        recording.lock().unwrap().handle(method, &message["params"]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(id: &str, url: &str, timestamp: f64) -> Value {
+        json!({
+            "requestId": id,
+            "timestamp": timestamp,
+            "wallTime": 1_700_000_000.0,
+            "request": { "method": "GET", "url": url, "headers": { "Accept": "*/*" } },
+        })
+    }
+
+    #[test]
+    fn to_har_serializes_a_finished_request() {
+        let mut recording = Recording::default();
+        recording.handle("Network.requestWillBeSent", &request("1", "https://example.com/", 10.0));
+        recording.handle("Network.responseReceived", &json!({
+            "requestId": "1",
+            "timestamp": 10.25,
+            "response": {
+                "status": 200,
+                "statusText": "OK",
+                "protocol": "h2",
+                "mimeType": "text/html",
+                "headers": { "content-type": "text/html" },
+            },
+        }));
+        recording.handle("Network.loadingFinished", &json!({
+            "requestId": "1",
+            "timestamp": 10.5,
+            "encodedDataLength": 1234.0,
+        }));
+
+        let har = recording.to_har();
+        assert_eq!(har["log"]["version"], "1.2");
+
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry["request"]["url"], "https://example.com/");
+        assert_eq!(entry["request"]["headers"], json!([{ "name": "Accept", "value": "*/*" }]));
+        assert_eq!(entry["response"]["status"], 200);
+        assert_eq!(entry["response"]["httpVersion"], "h2");
+        assert_eq!(entry["response"]["content"]["size"], 1234.0);
+        assert_eq!(entry["time"], 500.0);
+        assert_eq!(entry["timings"]["wait"], 250.0);
+        assert_eq!(entry["timings"]["receive"], 250.0);
+        assert!(entry.get("_error").is_none());
+    }
+
+    #[test]
+    fn to_har_keeps_each_redirect_hop() {
+        let mut recording = Recording::default();
+        recording.handle("Network.requestWillBeSent", &request("1", "http://example.com/", 1.0));
+        let mut redirected = request("1", "https://example.com/", 1.5);
+        redirected["redirectResponse"] = json!({
+            "status": 301,
+            "headers": { "Location": "https://example.com/" },
+        });
+        recording.handle("Network.requestWillBeSent", &redirected);
+
+        let har = recording.to_har();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["response"]["status"], 301);
+        assert_eq!(entries[0]["response"]["redirectURL"], "https://example.com/");
+        assert_eq!(entries[1]["request"]["url"], "https://example.com/");
+    }
+
+    #[test]
+    fn to_har_records_failures_and_ignores_unknown_requests() {
+        let mut recording = Recording::default();
+        recording.handle("Network.requestWillBeSent", &request("1", "https://example.invalid/", 1.0));
+        recording.handle("Network.loadingFailed", &json!({
+            "requestId": "1",
+            "timestamp": 2.0,
+            "errorText": "net::ERR_NAME_NOT_RESOLVED",
+        }));
+        recording.handle("Network.loadingFinished", &json!({ "requestId": "2", "timestamp": 3.0 }));
+
+        let har = recording.to_har();
+        let entries = har["log"]["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0]["_error"], "net::ERR_NAME_NOT_RESOLVED");
+        assert_eq!(entries[0]["response"]["status"], 0);
+    }
+
+    #[test]
+    fn in_flight_lists_unfinished_requests() {
+        let mut recording = Recording::default();
+        recording.handle("Network.requestWillBeSent", &request("1", "https://example.com/", 1.0));
+        recording.handle("Network.requestWillBeSent", &request("2", "https://example.com/app.js", 1.1));
+        recording.handle("Network.requestWillBeSent", &request("3", "https://example.com/api", 1.2));
+        recording.handle("Network.loadingFinished", &json!({ "requestId": "1", "timestamp": 2.0 }));
+        recording.handle("Network.loadingFailed", &json!({ "requestId": "3", "timestamp": 2.0 }));
+
+        assert_eq!(recording.in_flight(), HashSet::from(["2".to_string()]));
+    }
+}
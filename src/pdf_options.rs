@@ -0,0 +1,153 @@
+use serde_json::{json, Value};
+
+/// A4 paper size in inches.
+const A4: (f64, f64) = (8.27, 11.69);
+
+/// Options for printing a page to PDF with `Tab::print_to_pdf`.
+///
+/// Defaults to A4 portrait with background graphics printed. Unset margins use
+/// Chrome's default of about 1cm.
+#[derive(Debug, Clone)]
+pub struct PdfOptions {
+    pub(crate) landscape: bool,
+    pub(crate) print_background: bool,
+    pub(crate) prefer_css_page_size: bool,
+    pub(crate) scale: Option<f64>,
+    pub(crate) paper_size: (f64, f64),
+    /// Top, right, bottom and left margins in inches.
+    pub(crate) margins: Option<[f64; 4]>,
+    pub(crate) page_ranges: Option<String>,
+}
+
+impl Default for PdfOptions {
+    fn default() -> Self {
+        Self {
+            landscape: false,
+            print_background: true,
+            prefer_css_page_size: false,
+            scale: None,
+            paper_size: A4,
+            margins: None,
+            page_ranges: None,
+        }
+    }
+}
+
+impl PdfOptions {
+    /// Create new PDF options with default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to print in landscape orientation.
+    pub fn with_landscape(mut self, landscape: bool) -> Self {
+        self.landscape = landscape;
+        self
+    }
+
+    /// Set whether to print background colors and images. Enabled by default.
+    pub fn with_print_background(mut self, print_background: bool) -> Self {
+        self.print_background = print_background;
+        self
+    }
+
+    /// Set whether a page size set with CSS `@page` takes precedence over the paper size.
+    pub fn with_prefer_css_page_size(mut self, prefer: bool) -> Self {
+        self.prefer_css_page_size = prefer;
+        self
+    }
+
+    /// Set the scale of the page rendering, between 0.1 and 2.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Set the paper size in inches, e.g. `8.5` by `11` for US Letter. Defaults to A4.
+    pub fn with_paper_size(mut self, width: f64, height: f64) -> Self {
+        self.paper_size = (width, height);
+        self
+    }
+
+    /// Set all four margins in inches.
+    pub fn with_margin(mut self, margin: f64) -> Self {
+        self.margins = Some([margin; 4]);
+        self
+    }
+
+    /// Set the top, right, bottom and left margins in inches.
+    pub fn with_margins(mut self, top: f64, right: f64, bottom: f64, left: f64) -> Self {
+        self.margins = Some([top, right, bottom, left]);
+        self
+    }
+
+    /// Set the pages to print, e.g. `"1-5, 8"`. All pages are printed by default.
+    pub fn with_page_ranges(mut self, ranges: impl Into<String>) -> Self {
+        self.page_ranges = Some(ranges.into());
+        self
+    }
+
+    /// The `Page.printToPDF` parameters for these options.
+    pub(crate) fn to_params(&self) -> Value {
+        let mut params = json!({
+            "landscape": self.landscape,
+            "printBackground": self.print_background,
+            "preferCSSPageSize": self.prefer_css_page_size,
+            "paperWidth": self.paper_size.0,
+            "paperHeight": self.paper_size.1,
+        });
+
+        if let Some(scale) = self.scale {
+            params["scale"] = json!(scale);
+        }
+        if let Some(margins) = self.margins {
+            for (side, margin) in ["marginTop", "marginRight", "marginBottom", "marginLeft"].into_iter().zip(margins) {
+                params[side] = json!(margin);
+            }
+        }
+        if let Some(ranges) = &self.page_ranges {
+            params["pageRanges"] = json!(ranges);
+        }
+
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_print_a4_portrait() {
+        let params = PdfOptions::new().to_params();
+
+        assert_eq!(params, json!({
+            "landscape": false,
+            "printBackground": true,
+            "preferCSSPageSize": false,
+            "paperWidth": 8.27,
+            "paperHeight": 11.69,
+        }));
+    }
+
+    #[test]
+    fn params_include_set_options() {
+        let params = PdfOptions::new()
+            .with_landscape(true)
+            .with_scale(0.5)
+            .with_paper_size(8.5, 11.0)
+            .with_margins(1.0, 0.5, 0.25, 0.0)
+            .with_page_ranges("1-5, 8")
+            .to_params();
+
+        assert_eq!(params["landscape"], true);
+        assert_eq!(params["scale"], 0.5);
+        assert_eq!(params["paperWidth"], 8.5);
+        assert_eq!(params["paperHeight"], 11.0);
+        assert_eq!(params["marginTop"], 1.0);
+        assert_eq!(params["marginRight"], 0.5);
+        assert_eq!(params["marginBottom"], 0.25);
+        assert_eq!(params["marginLeft"], 0.0);
+        assert_eq!(params["pageRanges"], "1-5, 8");
+    }
+}
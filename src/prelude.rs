@@ -0,0 +1,28 @@
+/*!
+The commonly used types of this crate, for glob importing.
+
+```no_run
+use cdp_html_shot::prelude::*;
+
+# async fn run() -> anyhow::Result<()> {
+let browser = Browser::new().await?;
+let options = CaptureOptions::new().with_format(ImageFormat::Png);
+let base64 = browser.capture_html_with_options("<h1>Hello world!</h1>", "h1", options).await?;
+# Ok(())
+# }
+```
+*/
+
+pub use crate::tab::Tab;
+pub use crate::types::{ConsoleMessage, Cookie, SameSite};
+pub use crate::element::{BoxModel, Element};
+pub use crate::viewport::Viewport;
+pub use crate::pdf_options::PdfOptions;
+pub use crate::browser::{Browser, BrowserBuilder, BrowserOptions};
+pub use crate::intercept::{InterceptAction, InterceptedRequest};
+pub use crate::watermark::{Watermark, WatermarkPosition};
+pub use crate::capture_options::{
+    CaptureOptions, ClipRegion, ClipScale, ClipUnits, ElementBackground, ImageFormat, DEFAULT_JPEG_QUALITY,
+};
+#[cfg(feature = "image")]
+pub use crate::image_utils::DiffResult;
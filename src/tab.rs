@@ -1,18 +1,52 @@
-use std::sync::Arc;
-use serde_json::json;
-use anyhow::{Context, Result};
+use tokio::time;
+use time::Duration;
+use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::task::JoinHandle;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use futures::stream::{self, Stream, StreamExt};
+use serde_json::{json, Value};
+use log::warn;
+use anyhow::{bail, Context, Result};
 
+use crate::image_utils;
 use crate::general_utils;
+use crate::types::{ConsoleMessage, Cookie};
 use crate::element::Element;
-use crate::transport::Transport;
-use crate::general_utils::next_id;
-use crate::transport_actor::TransportResponse;
+use crate::viewport::Viewport;
+use crate::pdf_options::PdfOptions;
+use crate::network::{self, Recording};
+use crate::intercept::{self, InterceptAction, InterceptedRequest};
+use crate::transport::{Timeouts, Transport};
+use crate::capture_options::{CaptureFormat, CaptureOptions, ClipRegion, ClipScale, ClipUnits, ImageFormat};
+use crate::transport_actor::{TargetMessage, TransportResponse};
+
+/// Captures above this many pixels are likely unintended and get a warning.
+const MAX_EXPECTED_PIXELS: f64 = 40_000_000.0;
 
 /// A tab instance.
 pub struct Tab {
     pub(crate) transport: Arc<Transport>,
     pub(crate) session_id: String,
     pub(crate) target_id: String,
+    /// The last viewport applied with `set_viewport`, if still in effect.
+    viewport: Mutex<Option<Viewport>>,
+    /// Overrides `captureBeyondViewport` for all captures of this tab when set.
+    capture_beyond_viewport: Mutex<Option<bool>>,
+    /// Default timeouts of this tab, initially those of the browser.
+    timeouts: Mutex<Timeouts>,
+    /// Whether the `Page` domain has been enabled for this tab's session.
+    page_enabled: AtomicBool,
+    /// Whether the `Runtime` domain has been enabled for this tab's session.
+    runtime_enabled: AtomicBool,
+    /// The task answering intercepted requests, if interception is enabled.
+    interceptor: Mutex<Option<JoinHandle<()>>>,
+    /// Network events collected since the last `start_network_recording`.
+    recording: Arc<Mutex<Recording>>,
+    /// The task feeding `recording`, if network recording is running.
+    recorder: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl Tab {
@@ -34,7 +68,7 @@ impl Tab {
     */
     pub(crate) async fn new(transport: Arc<Transport>) -> Result<Self> {
         let TransportResponse::Response(res) = transport.send(json!({
-            "id": next_id(),
+            "id": transport.next_id(),
             "method": "Target.createTarget",
             "params": {
                 "url": "about:blank"
@@ -48,8 +82,13 @@ impl Tab {
             .as_str()
             .unwrap();
 
+        Self::attach(transport, target_id).await
+    }
+
+    /// Attach to an existing page target.
+    pub(crate) async fn attach(transport: Arc<Transport>, target_id: &str) -> Result<Self> {
         let TransportResponse::Response(res) = transport.send(json!({
-            "id": next_id(),
+            "id": transport.next_id(),
             "method": "Target.attachToTarget",
             "params": {
                 "targetId": target_id
@@ -62,15 +101,26 @@ impl Tab {
             .unwrap();
 
         Ok(Self {
+            timeouts: Mutex::new(transport.timeouts),
+            page_enabled: AtomicBool::new(false),
+            runtime_enabled: AtomicBool::new(false),
             transport,
             session_id: String::from(session_id),
             target_id: String::from(target_id),
+            viewport: Mutex::new(None),
+            capture_beyond_viewport: Mutex::new(None),
+            interceptor: Mutex::new(None),
+            recording: Arc::new(Mutex::new(Recording::default())),
+            recorder: Mutex::new(None),
         })
     }
 
     /**
     Set the content of the tab.
 
+    Accepts `&str` as well as an owned `String`. The content is escaped in a single pass
+    while being copied into the script, rather than in one pass per escaped character.
+
     # Example
     ```no_run
     use cdp_html_shot::Browser;
@@ -85,21 +135,21 @@ impl Tab {
     }
     ```
     */
-    pub async fn set_content(&self, content: &str) -> Result<&Self> {
-        let content = match (content.contains('`'), content.contains("${")) {
-            (true, true) => &content.replace('`', "${BACKTICK}").replace("${", "$ {"),
-            (true, false) => &content.replace('`', "${BACKTICK}"),
-            (false, true) => &content.replace("${", "$ {"),
-            (false, false) => content,
-        };
+    pub async fn set_content(&self, content: impl AsRef<str>) -> Result<&Self> {
+        let content = content.as_ref();
+        let timeout = self.timeouts().navigation;
+        let timeout_ms = timeout.as_millis();
 
-        let expression = format!(
-            r#"
-    (async () => {{
-        try {{
+        let prefix = r#"
+    (async () => {
+        try {
             const BACKTICK = '`';
+            const DOLLAR_BRACE = '${';
             document.open();
-            document.write(String.raw`{content}`);
+            document.write(String.raw`"#;
+
+        let suffix = format!(
+            r#"`);
             document.close();
 
             await Promise.race([
@@ -152,7 +202,7 @@ impl Tab {
                 }}),
 
                 new Promise((_, reject) => {{
-                    setTimeout(() => reject(new Error('Timeout')), 30000);
+                    setTimeout(() => reject(new Error('Timeout')), {timeout_ms});
                 }})
             ]);
 
@@ -164,7 +214,13 @@ impl Tab {
     "#
         );
 
-        let msg_id = next_id();
+        // Reserve room for the content up front, as it can be hundreds of KB.
+        let mut expression = String::with_capacity(prefix.len() + content.len() + suffix.len() + 64);
+        expression.push_str(prefix);
+        escape_template_literal(content, &mut expression);
+        expression.push_str(&suffix);
+
+        let msg_id = self.transport.next_id();
         let msg = json!({
             "id": msg_id,
             "method": "Runtime.evaluate",
@@ -174,13 +230,27 @@ impl Tab {
             }
         }).to_string();
 
-        general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+        general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, timeout).await?;
 
         Ok(self)
     }
 
+    /// Send a CDP command to the tab and return its `result` object.
+    ///
+    /// A protocol error reply (e.g. a detached node) is returned as an error.
+    pub(crate) async fn send_cmd(&self, method: &str, params: Value) -> Result<Value> {
+        self.send_cmd_within(method, params, self.timeouts().command).await
+    }
+
+    /// Like `send_cmd`, but waits up to `timeout` for the reply.
+    pub(crate) async fn send_cmd_within(&self, method: &str, params: Value, timeout: Duration) -> Result<Value> {
+        general_utils::send_cmd(self.transport.clone(), &self.session_id, method, params, timeout).await
+    }
+
     /**
-    Find an element by CSS selector.
+    Evaluate a JavaScript expression in the tab and return its value.
+
+    Promises are awaited, and a thrown exception is returned as an error.
 
     # Example
     ```no_run
@@ -191,50 +261,115 @@ impl Tab {
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
         let tab = browser.new_tab().await?;
-        let element = tab.find_element("h1").await?;
+        let value = tab.evaluate("1 + 2").await?;
+        assert_eq!(value, 3);
         Ok(())
     }
     ```
     */
-    pub async fn find_element(&self, selector: &str) -> Result<Element> {
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "DOM.getDocument",
-            "params": {}
-        }).to_string();
+    pub async fn evaluate(&self, expression: &str) -> Result<Value> {
+        self.evaluate_within(expression, self.timeouts().command).await
+    }
 
-        let res = general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+    /// Like `evaluate`, but waits up to `timeout` for the result.
+    pub(crate) async fn evaluate_within(&self, expression: &str, timeout: Duration) -> Result<Value> {
+        let res = self.send_cmd_within("Runtime.evaluate", json!({
+            "expression": expression,
+            "awaitPromise": true,
+            "returnByValue": true
+        }), timeout).await?;
 
-        let msg = general_utils::serde_msg(&res);
-        let node_id = msg["result"]["root"]["nodeId"]
-            .as_u64()
-            .unwrap();
+        if let Some(exception) = res.get("exceptionDetails") {
+            return Err(anyhow::anyhow!("Evaluation failed: {}", exception["exception"]["description"]
+                .as_str()
+                .or(exception["text"].as_str())
+                .unwrap_or("unknown error")));
+        }
 
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "DOM.querySelector",
-            "params": {
-                "nodeId": node_id,
-                "selector": selector
+        Ok(res["result"]["value"].clone())
+    }
+
+    /**
+    Stream the console messages and uncaught exceptions of the page from now on, e.g. to
+    find out why a page renders blank.
+
+    The stream ends when the connection is closed; drop it to stop listening. Messages
+    logged before the first call may be replayed, as enabling the `Runtime` domain reports them.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+    use futures::StreamExt;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        let mut console = Box::pin(tab.on_console().await?);
+        tokio::spawn(async move {
+            while let Some(message) = console.next().await {
+                eprintln!("[{}] {}", message.level, message.text);
             }
-        }).to_string();
+        });
+
+        tab.set_content("<script>console.warn('missing data', 42)</script>").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn on_console(&self) -> Result<impl Stream<Item = ConsoleMessage>> {
+        let mut console = self.transport.subscribe(&self.session_id, "Runtime.consoleAPICalled").await?;
+        let mut exceptions = self.transport.subscribe(&self.session_id, "Runtime.exceptionThrown").await?;
+        self.enable_runtime().await?;
 
-        let res = general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+        let events = stream::select(
+            stream::poll_fn(move |cx| console.poll_recv(cx)),
+            stream::poll_fn(move |cx| exceptions.poll_recv(cx)),
+        );
 
-        let msg = general_utils::serde_msg(&res);
+        Ok(events.filter_map(|event| async move {
+            let message = general_utils::serde_msg(&event);
+            ConsoleMessage::from_event(message["method"].as_str()?, &message["params"])
+        }))
+    }
 
-        let node_id = match msg["result"]["nodeId"].as_u64() {
-            Some(node_id) => node_id,
-            None => return Err(anyhow::anyhow!("Element not found")),
-        };
+    /**
+    Call a JavaScript function declaration with `args` and return its value.
 
-        Element::new(self, node_id).await
+    The arguments are passed as JSON, so user data needs no escaping into the expression.
+    Promises are awaited, and a thrown exception is returned as an error.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+    use serde_json::json;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        let value = tab.call_function("(a, b) => a + b", &[json!(1), json!(2)]).await?;
+        assert_eq!(value, 3);
+
+        let title = "It's \"quoted\" </script>";
+        tab.call_function("(title) => { document.title = title; }", &[json!(title)]).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn call_function(&self, fn_decl: &str, args: &[Value]) -> Result<Value> {
+        // JSON is valid JavaScript, so the arguments can't break out of the array literal.
+        let args = serde_json::to_string(args)?;
+        self.evaluate(&format!("({}).apply(globalThis, {})", fn_decl, args)).await
     }
 
     /**
-    Close the tab.
+    Wait until all images of the page are decoded, so captures don't show broken images.
+
+    Images that fail to load are ignored. Fails if decoding takes longer than `timeout_ms`.
 
     # Example
     ```no_run
@@ -245,67 +380,152 @@ impl Tab {
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
         let tab = browser.new_tab().await?;
-        tab.close().await?;
+        tab.set_content("<img src='https://www.rust-lang.org/logos/rust-logo-512x512.png'>").await?;
+        tab.wait_for_images(10_000).await?;
         Ok(())
     }
     ```
     */
-    pub async fn activate(&self) -> Result<&Self> {
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "Target.activateTarget",
-            "params": {
-                "targetId": self.target_id
-            }
-        }).to_string();
+    pub async fn wait_for_images(&self, timeout_ms: u64) -> Result<&Self> {
+        let expression = format!(r#"
+    Promise.race([
+        Promise.all([...document.images].map(img => img.decode().catch(() => {{}}))).then(() => true),
+        new Promise(resolve => setTimeout(() => resolve(false), {timeout_ms}))
+    ])
+    "#);
 
-        general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+        // Leave the page-side timeout room to report before the transport gives up.
+        let timeout = Duration::from_millis(timeout_ms) + self.timeouts().command;
+        let decoded = self.evaluate_within(&expression, timeout).await?;
+
+        if decoded != Value::Bool(true) {
+            return Err(anyhow::anyhow!("Timeout while waiting for images to decode"));
+        }
 
         Ok(self)
     }
 
     /**
-    Navigate to a URL.
+    Wait until the tab has had no network requests in flight for `idle_ms`.
 
-    # Warning
+    Enables the `Network` domain and tracks requests as they start and finish or fail,
+    so lazy images, fonts and late API calls are done before a capture. Fails after
+    `timeout_ms`, e.g. when a long-polling request never finishes.
 
-    This API does not wait for the page to load, it is only used to navigate to local HTML files,
-    which is convenient for getting font and other resources.
+    Requests started before this call, e.g. by a navigation, are only tracked while a
+    network recording is running, as Chrome doesn't report requests already in flight.
+    Call `start_network_recording` before navigating to wait for a page to finish loading.
 
     # Example
     ```no_run
     use cdp_html_shot::Browser;
     use anyhow::Result;
-    use tokio::time;
 
     #[tokio::main]
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
         let tab = browser.new_tab().await?;
+        tab.start_network_recording().await?;
         tab.goto("https://www.rust-lang.org/").await?;
-        time::sleep(time::Duration::from_secs(5)).await;
+        tab.wait_for_network_idle(500, 10_000).await?;
         Ok(())
     }
     ```
     */
-    pub async fn goto(&self, url: &str) -> Result<&Self> {
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "Page.navigate",
-            "params": {
-                "url": url
+    pub async fn wait_for_network_idle(&self, idle_ms: u64, timeout_ms: u64) -> Result<&Self> {
+        let mut started = self.transport.subscribe(&self.session_id, "Network.requestWillBeSent").await?;
+        let mut finished = self.transport.subscribe(&self.session_id, "Network.loadingFinished").await?;
+        let mut failed = self.transport.subscribe(&self.session_id, "Network.loadingFailed").await?;
+
+        self.send_cmd("Network.enable", json!({})).await?;
+
+        let request_id = |event: TargetMessage| {
+            general_utils::serde_msg(&event)["params"]["requestId"].as_str().unwrap_or_default().to_string()
+        };
+
+        let deadline = time::Instant::now() + Duration::from_millis(timeout_ms);
+        let idle = Duration::from_millis(idle_ms);
+        let mut in_flight = if self.recorder.lock().unwrap().is_some() {
+            self.recording.lock().unwrap().in_flight()
+        } else {
+            HashSet::new()
+        };
+        let mut last_activity = time::Instant::now();
+
+        loop {
+            tokio::select! {
+                Some(event) = started.recv() => {
+                    in_flight.insert(request_id(event));
+                }
+                Some(event) = finished.recv() => {
+                    in_flight.remove(&request_id(event));
+                }
+                Some(event) = failed.recv() => {
+                    in_flight.remove(&request_id(event));
+                }
+                _ = time::sleep_until(last_activity + idle), if in_flight.is_empty() => {
+                    return Ok(self);
+                }
+                _ = time::sleep_until(deadline) => {
+                    return Err(anyhow::anyhow!(
+                        "Timeout while waiting for network idle, {} requests still in flight",
+                        in_flight.len()
+                    ));
+                }
             }
-        }).to_string();
 
-        general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+            last_activity = time::Instant::now();
+        }
+    }
 
-        Ok(self)
+    /**
+    Wait until the JavaScript `expression` evaluates to exactly `expected`.
+
+    Polls every 100ms and compares JSON values deeply, so readiness signals such as a
+    status string or a count can be matched precisely. Fails after `timeout_ms` with an
+    error showing the last value observed.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use serde_json::json;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://example.com").await?;
+        tab.wait_for_value("document.readyState", json!("complete"), 10_000).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn wait_for_value(&self, expression: &str, expected: Value, timeout_ms: u64) -> Result<&Self> {
+        let deadline = time::Instant::now() + Duration::from_millis(timeout_ms);
+
+        loop {
+            let value = self.evaluate(expression).await?;
+            if value == expected {
+                return Ok(self);
+            }
+            if time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!(
+                    "Timeout while waiting for {} to equal {}, last value was {}",
+                    expression, expected, value
+                ));
+            }
+            time::sleep(Duration::from_millis(100)).await;
+        }
     }
 
     /**
-    Close the tab.
+    Evaluate a JavaScript expression and return its value as a string.
+
+    Returns `None` for `null` and `undefined`, the text of strings, and the JSON text of
+    numbers and booleans. Objects and arrays are an error, use [`evaluate`] for those.
+
+    [`evaluate`]: struct.Tab.html#method.evaluate
 
     # Example
     ```no_run
@@ -316,23 +536,2073 @@ impl Tab {
     async fn main() -> Result<()> {
         let browser = Browser::new().await?;
         let tab = browser.new_tab().await?;
-        tab.close().await?;
+        let lang = tab.evaluate_as_string("document.documentElement.getAttribute('lang')").await?;
+        assert_eq!(lang, None);
         Ok(())
     }
     ```
     */
-    pub async fn close(&self) -> Result<()> {
-        let msg_id = next_id();
-        let msg = json!({
-            "id": msg_id,
-            "method": "Target.closeTarget",
-            "params": {
-                "targetId": self.target_id
+    pub async fn evaluate_as_string(&self, expression: &str) -> Result<Option<String>> {
+        match self.evaluate(expression).await? {
+            Value::Null => Ok(None),
+            Value::String(value) => Ok(Some(value)),
+            value @ (Value::Bool(_) | Value::Number(_)) => Ok(Some(value.to_string())),
+            value => Err(anyhow::anyhow!("Expected a primitive value, got: {}", value)),
+        }
+    }
+
+    /// Get the title of the page, which is empty if the page has no title.
+    pub async fn title(&self) -> Result<String> {
+        Ok(self.evaluate_as_string("document.title").await?.unwrap_or_default())
+    }
+
+    /**
+    Capture a screenshot of the tab's viewport, of the clip region set in `options`
+    with `with_clip` or `with_clip_from_selector`, or of the whole document with
+    `with_full_page`.
+
+    Clip coordinates are relative to the document. When the clip reaches outside the
+    visible viewport (e.g. below the fold), `captureBeyondViewport` is enabled automatically.
+
+    Returns the base64-encoded image data.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions, ClipRegion};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let options = CaptureOptions::new()
+            .with_clip(ClipRegion::new(0.0, 0.0, 400.0, 200.0));
+        let base64 = tab.screenshot(options).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot(&self, options: CaptureOptions) -> Result<String> {
+        let data = self.screenshot_bytes(options).await?;
+
+        Ok(general_utils::encode_base64(&data))
+    }
+
+    /**
+    Like [`screenshot`], but return the decoded image bytes instead of base64.
+
+    [`screenshot`]: struct.Tab.html#method.screenshot
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let jpeg = tab.screenshot_bytes(CaptureOptions::new()).await?;
+        std::fs::write("page.jpeg", jpeg)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_bytes(&self, options: CaptureOptions) -> Result<Vec<u8>> {
+        options.check_supported()?;
+        let format = options.capture_format();
+
+        let mut params = json!({
+            "format": format.as_str(),
+            "fromSurface": options.from_surface.unwrap_or(true),
+            "captureBeyondViewport": false,
+        });
+
+        if format != CaptureFormat::Png {
+            params["quality"] = json!(options.quality());
+        }
+
+        let clip = if options.full_page {
+            let (width, height) = self.content_size().await?;
+            let scale = options.clip.map_or(1.0, |clip| clip.scale);
+            params["captureBeyondViewport"] = json!(true);
+            Some(ClipRegion::new(0.0, 0.0, width, height).with_scale(scale))
+        } else {
+            match &options.clip_selector {
+                Some(selector) => Some(self.find_element(selector).await?.bounding_box().await?),
+                None => options.clip,
             }
-        }).to_string();
+        };
+
+        if let Some(clip) = clip {
+            let (device_pixel_ratio, viewport) = self.viewport_metrics().await?;
+
+            // CDP expects CSS pixels, so device pixels are divided by the device pixel ratio.
+            let ratio = match clip.units {
+                ClipUnits::Css => 1.0,
+                ClipUnits::Device => device_pixel_ratio,
+            };
+
+            // CDP multiplies the clip scale by the device pixel ratio.
+            let scale = match clip.scale_mode {
+                ClipScale::Multiply => clip.scale,
+                ClipScale::Replace => clip.scale / device_pixel_ratio,
+            };
+
+            let (mut x, mut y) = (clip.x / ratio, clip.y / ratio);
+
+            if let (Some(frame_id), None, false) = (&options.frame_id, &options.clip_selector, options.full_page) {
+                let (offset_x, offset_y) = self.frame_offset(frame_id).await?;
+                x += offset_x;
+                y += offset_y;
+            }
+
+            let ClipRegion { x, y, width, height, .. } = ClipRegion::new(x, y, clip.width / ratio, clip.height / ratio)
+                .snap_to_pixels(scale * device_pixel_ratio);
+
+            params["clip"] = json!({
+                "x": x,
+                "y": y,
+                "width": width,
+                "height": height,
+                "scale": scale
+            });
 
-        general_utils::send_and_get_msg(self.transport.clone(), msg_id, &self.session_id, msg).await?;
+            let (output_width, output_height) = (
+                width * scale * device_pixel_ratio,
+                height * scale * device_pixel_ratio,
+            );
+            if output_width * output_height > MAX_EXPECTED_PIXELS {
+                warn!(
+                    "Capture will be {:.0}x{:.0} pixels: the clip scale ({}) multiplies the device pixel ratio ({}); \
+                    use ClipScale::Replace for a fixed scale",
+                    output_width, output_height, clip.scale, device_pixel_ratio,
+                );
+            }
+
+            // A clip outside the visible viewport would otherwise come out blank or cut off.
+            if exceeds_viewport(&ClipRegion::new(x, y, width, height), &viewport) {
+                params["captureBeyondViewport"] = json!(true);
+            }
+        }
+
+        let base64 = self.capture_screenshot(
+            params,
+            options.omit_background,
+            options.capture_beyond_viewport,
+            options.empty_retries(),
+        ).await;
+
+        // Capturing beyond the viewport resizes the page temporarily, which drops the
+        // device metrics override in some Chrome versions, so it is applied again.
+        if options.full_page {
+            let viewport = self.viewport.lock().unwrap().clone();
+            if let Some(viewport) = viewport {
+                self.apply_device_metrics(&viewport).await?;
+            }
+        }
+
+        options.finish_capture(general_utils::decode_capture(&base64?)?)
+    }
+
+    /**
+    Capture a screenshot with `options` as in [`screenshot`] and write the image to
+    `path`, creating missing parent directories.
+
+    The format is taken from `options` only, whatever the extension of `path`.
+
+    [`screenshot`]: struct.Tab.html#method.screenshot
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions, ImageFormat};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let options = CaptureOptions::new().with_format(ImageFormat::Png);
+        tab.save_screenshot("cache/page.png", options).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn save_screenshot(&self, path: impl AsRef<Path>, options: CaptureOptions) -> Result<()> {
+        let img_data = self.screenshot_bytes(options).await?;
+
+        general_utils::write_creating_dirs(path.as_ref(), &img_data)
+    }
+
+    /**
+    Capture the elements matching each of `selectors` in the tab's current page, without
+    reloading it between captures.
+
+    Returns each selector with the decoded image data of its element, in the order of
+    `selectors`. A selector that matches nothing, or whose capture fails, gets an error
+    entry instead of aborting the remaining captures.
 
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div id='chart'>Chart</div><div id='summary'>Summary</div>").await?;
+
+        for (selector, image) in tab.screenshot_all(&["#chart", "#summary"], CaptureOptions::new()).await {
+            match image {
+                Ok(image) => std::fs::write(format!("{}.jpeg", &selector[1..]), image)?,
+                Err(e) => eprintln!("{selector}: {e}"),
+            }
+        }
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_all(&self, selectors: &[&str], options: CaptureOptions) -> Vec<(String, Result<Vec<u8>>)> {
+        let mut images = Vec::with_capacity(selectors.len());
+        for selector in selectors {
+            let image = match self.find_element(selector).await {
+                Ok(element) => element.screenshot_bytes(options.clone()).await,
+                Err(e) => Err(e),
+            };
+            images.push((selector.to_string(), image));
+        }
+
+        images
+    }
+
+    /**
+    List the child frames of the page as `(frame_id, url)` pairs, depth first.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions, ClipRegion};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<iframe srcdoc='<h1>Nested</h1>'></iframe>").await?;
+
+        let (frame_id, _url) = tab.frames().await?.remove(0);
+        let options = CaptureOptions::new()
+            .with_clip(ClipRegion::new(0.0, 0.0, 100.0, 50.0))
+            .with_frame(frame_id);
+        tab.screenshot(options).await?;
         Ok(())
     }
-}
\ No newline at end of file
+    ```
+    */
+    pub async fn frames(&self) -> Result<Vec<(String, String)>> {
+        fn collect(node: &Value, frames: &mut Vec<(String, String)>) {
+            for child in node["childFrames"].as_array().into_iter().flatten() {
+                let frame = &child["frame"];
+                if let Some(id) = frame["id"].as_str() {
+                    frames.push((id.to_string(), frame["url"].as_str().unwrap_or_default().to_string()));
+                }
+                collect(child, frames);
+            }
+        }
+
+        let res = self.send_cmd("Page.getFrameTree", json!({})).await?;
+
+        let mut frames = Vec::new();
+        collect(&res["frameTree"], &mut frames);
+
+        Ok(frames)
+    }
+
+    /// Position of a frame's content box in page coordinates.
+    async fn frame_offset(&self, frame_id: &str) -> Result<(f64, f64)> {
+        let owner = self.send_cmd("DOM.getFrameOwner", json!({
+            "frameId": frame_id
+        })).await?;
+
+        let res = self.send_cmd("DOM.getBoxModel", json!({
+            "backendNodeId": owner["backendNodeId"]
+        })).await?;
+
+        let content = &res["model"]["content"];
+        Ok((
+            content[0].as_f64().context("Failed to get frame x")?,
+            content[1].as_f64().context("Failed to get frame y")?,
+        ))
+    }
+
+    /**
+    Capture the page while the left mouse button is held down on the element matching
+    `selector`, then release it.
+
+    Shows states that only exist during a press, such as `:active` styles or
+    press-and-hold tooltips. The button is released even if the capture fails.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<style>button:active { background: red }</style><button>Hold</button>").await?;
+
+        let base64 = tab
+            .press_and_capture("button", CaptureOptions::new().with_clip_from_selector("button"))
+            .await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn press_and_capture(&self, selector: &str, options: CaptureOptions) -> Result<String> {
+        let element = self.find_element(selector).await?;
+        element.scroll_into_view().await?;
+
+        let rect = element.client_rect().await?;
+        let (x, y) = (rect.x + rect.width / 2.0, rect.y + rect.height / 2.0);
+
+        let mouse_event = |kind: &str| json!({
+            "type": kind,
+            "x": x,
+            "y": y,
+            "button": "left",
+            "buttons": if kind == "mouseReleased" { 0 } else { 1 },
+            "clickCount": 1
+        });
+
+        self.send_cmd("Input.dispatchMouseEvent", json!({ "type": "mouseMoved", "x": x, "y": y })).await?;
+        self.send_cmd("Input.dispatchMouseEvent", mouse_event("mousePressed")).await?;
+
+        let capture = self.screenshot(options).await;
+
+        self.send_cmd("Input.dispatchMouseEvent", mouse_event("mouseReleased")).await?;
+
+        capture
+    }
+
+    /**
+    Capture the page while `key` is held down, then release it.
+
+    `key` is a DOM key value such as `"Shift"` or `"a"`. The key is released even if
+    the capture fails.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        let base64 = tab.hold_key_and_capture("Shift", CaptureOptions::new()).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn hold_key_and_capture(&self, key: &str, options: CaptureOptions) -> Result<String> {
+        self.send_cmd("Input.dispatchKeyEvent", json!({ "type": "keyDown", "key": key })).await?;
+
+        let capture = self.screenshot(options).await;
+
+        self.send_cmd("Input.dispatchKeyEvent", json!({ "type": "keyUp", "key": key })).await?;
+
+        capture
+    }
+
+    /// The device pixel ratio of the page, which may be fractional.
+    pub(crate) async fn device_pixel_ratio(&self) -> Result<f64> {
+        self.evaluate("window.devicePixelRatio").await?
+            .as_f64()
+            .context("Failed to get devicePixelRatio")
+    }
+
+    /// The device pixel ratio and the visible viewport in page coordinates, fetched
+    /// together so a clip capture needs a single round trip for both.
+    async fn viewport_metrics(&self) -> Result<(f64, ClipRegion)> {
+        let metrics = self.evaluate(
+            "({ dpr: devicePixelRatio, x: visualViewport.pageLeft, y: visualViewport.pageTop, \
+                width: visualViewport.width, height: visualViewport.height })"
+        ).await?;
+        let get = |key: &str| metrics[key]
+            .as_f64()
+            .with_context(|| format!("Failed to get viewport {}", key));
+
+        Ok((get("dpr")?, ClipRegion::new(get("x")?, get("y")?, get("width")?, get("height")?)))
+    }
+
+    /**
+    Set the default timeout for the reply to each command of this tab.
+
+    Defaults to the browser's command timeout. Methods taking an explicit timeout,
+    such as `wait_for_selector`, are unaffected.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use std::time::Duration;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_default_timeout(Duration::from_secs(30))
+            .set_default_navigation_timeout(Duration::from_secs(120));
+        Ok(())
+    }
+    ```
+    */
+    pub fn set_default_timeout(&self, timeout: Duration) -> &Self {
+        self.timeouts.lock().unwrap().command = timeout;
+        self
+    }
+
+    /// Set the default timeout for page loads of this tab, e.g. in `set_content`,
+    /// `goto_and_wait` and `reload`.
+    ///
+    /// Defaults to the browser's navigation timeout.
+    pub fn set_default_navigation_timeout(&self, timeout: Duration) -> &Self {
+        self.timeouts.lock().unwrap().navigation = timeout;
+        self
+    }
+
+    /// The current default timeouts of this tab.
+    pub(crate) fn timeouts(&self) -> Timeouts {
+        *self.timeouts.lock().unwrap()
+    }
+
+    /**
+    Force whether captures of this tab include content beyond the viewport.
+
+    By default (`None`), element captures always capture beyond the viewport and
+    [`screenshot`] does so only when its clip extends past the viewport. `Some(value)`
+    applies `value` to every capture of this tab instead, unless a capture sets
+    `CaptureOptions::with_capture_beyond_viewport`.
+
+    [`screenshot`]: struct.Tab.html#method.screenshot
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_capture_beyond_viewport(Some(false));
+        Ok(())
+    }
+    ```
+    */
+    pub fn set_capture_beyond_viewport(&self, enabled: Option<bool>) -> &Self {
+        *self.capture_beyond_viewport.lock().unwrap() = enabled;
+        self
+    }
+
+    /// The `captureBeyondViewport` override of this tab, if any.
+    pub fn capture_beyond_viewport(&self) -> Option<bool> {
+        *self.capture_beyond_viewport.lock().unwrap()
+    }
+
+    /// Issue `Page.captureScreenshot` with the given parameters and return the base64 data.
+    ///
+    /// A capture whose data is empty or too short to hold an image, as happens during
+    /// compositor hiccups, is retried up to `retries` times after a short delay.
+    ///
+    /// `capture_beyond_viewport` overrides the tab's override, which overrides `params`.
+    pub(crate) async fn capture_screenshot(
+        &self,
+        mut params: Value,
+        omit_background: bool,
+        capture_beyond_viewport: Option<bool>,
+        retries: u32,
+    ) -> Result<String> {
+        if let Some(enabled) = capture_beyond_viewport.or(self.capture_beyond_viewport()) {
+            params["captureBeyondViewport"] = json!(enabled);
+        }
+
+        // Valid image data is never this short: even the RIFF and chunk headers of a WebP
+        // take 24 bytes, i.e. 32 base64 characters.
+        const MIN_BASE64_LEN: usize = 32;
+
+        let mut attempt = 0;
+        loop {
+            let base64 = self.capture_screenshot_once(&params, omit_background).await?;
+
+            if base64.len() >= MIN_BASE64_LEN {
+                return Ok(base64);
+            }
+            if attempt >= retries {
+                return Err(anyhow::anyhow!("No image data received after {} attempts", attempt + 1));
+            }
+
+            attempt += 1;
+            warn!("Empty capture data, retrying ({}/{})", attempt, retries);
+            time::sleep(Duration::from_millis(100 * attempt as u64)).await;
+        }
+    }
+
+    async fn capture_screenshot_once(&self, params: &Value, omit_background: bool) -> Result<String> {
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "Page.captureScreenshot",
+            "params": params
+        }).to_string();
+
+        self.activate().await?;
+
+        if omit_background {
+            self.send_cmd("Emulation.setDefaultBackgroundColorOverride", json!({
+                "color": { "r": 0, "g": 0, "b": 0, "a": 0 }
+            })).await?;
+        }
+
+        let res = general_utils::send_and_get_msg_within(
+            self.transport.clone(),
+            msg_id,
+            &self.session_id,
+            msg,
+            self.timeouts().command,
+        ).await;
+
+        if omit_background {
+            self.send_cmd("Emulation.setDefaultBackgroundColorOverride", json!({})).await?;
+        }
+
+        let res = res?;
+
+        let msg = general_utils::serde_msg(&res);
+        let base64 = msg["result"]
+            .get("data")
+            .context("Failed to get data")?
+            .as_str()
+            .context("Failed to convert data to string")?
+            .to_string();
+
+        Ok(base64)
+    }
+
+    /**
+    Emulate the given viewport and device metrics.
+
+    The last applied viewport is remembered, and CDP calls are skipped when the
+    device metrics or touch emulation are unchanged, so reusing a tab in a loop is cheap.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, Viewport};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        let viewport = Viewport::new(390, 664)
+            .with_device_scale_factor(3.0)
+            .with_mobile(true)
+            .with_touch(true)
+            .with_screen_size(390, 844);
+        tab.set_viewport(&viewport).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_viewport(&self, viewport: &Viewport) -> Result<&Self> {
+        let last = self.viewport.lock().unwrap().clone();
+        let metrics_changed = last.as_ref().is_none_or(|last| !last.same_metrics(viewport));
+        let touch_changed = last.as_ref().is_none_or(|last| last.has_touch != viewport.has_touch);
+        let user_agent_changed = last
+            .as_ref()
+            .map_or(viewport.user_agent.is_some(), |last| last.user_agent != viewport.user_agent);
+
+        if metrics_changed {
+            self.apply_device_metrics(viewport).await?;
+        }
+
+        if touch_changed {
+            self.send_cmd("Emulation.setTouchEmulationEnabled", json!({
+                "enabled": viewport.has_touch
+            })).await?;
+        }
+
+        if user_agent_changed {
+            // An empty user agent restores the browser's own.
+            self.send_cmd("Emulation.setUserAgentOverride", json!({
+                "userAgent": viewport.user_agent.as_deref().unwrap_or_default()
+            })).await?;
+        }
+
+        *self.viewport.lock().unwrap() = Some(viewport.clone());
+
+        Ok(self)
+    }
+
+    async fn apply_device_metrics(&self, viewport: &Viewport) -> Result<()> {
+        if viewport.orientation_mismatch() {
+            warn!(
+                "Viewport {}x{} is {} but its dimensions are not; use Viewport::portrait or Viewport::landscape",
+                viewport.width,
+                viewport.height,
+                if viewport.is_landscape { "landscape" } else { "portrait" },
+            );
+        }
+
+        let mut params = json!({
+            "width": viewport.width,
+            "height": viewport.height,
+            "deviceScaleFactor": viewport.device_scale_factor,
+            "mobile": viewport.is_mobile,
+            "screenOrientation": if viewport.is_landscape {
+                json!({ "type": "landscapePrimary", "angle": 90 })
+            } else {
+                json!({ "type": "portraitPrimary", "angle": 0 })
+            }
+        });
+
+        if let Some(screen_width) = viewport.screen_width {
+            params["screenWidth"] = json!(screen_width);
+        }
+        if let Some(screen_height) = viewport.screen_height {
+            params["screenHeight"] = json!(screen_height);
+        }
+
+        self.send_cmd("Emulation.setDeviceMetricsOverride", params).await?;
+
+        Ok(())
+    }
+
+    /**
+    Show or hide scrollbars in this tab, overriding the browser default.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_scrollbars_visible(true).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_scrollbars_visible(&self, visible: bool) -> Result<&Self> {
+        self.send_cmd("Emulation.setScrollbarsHidden", json!({
+            "hidden": !visible
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Emulate a CSS media type, e.g. `"print"`, and media feature values such as
+    `("prefers-color-scheme", "dark")` or `("prefers-reduced-motion", "reduce")`.
+
+    A `media_type` of `None` keeps the real media type. Each call replaces all previous
+    media overrides, so pass an empty `features` slice and `None` to clear them.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<style>@media screen { h1 { display: none } }</style><h1>Print only</h1>").await?;
+        tab.emulate_media(Some("print"), &[("prefers-color-scheme", "dark")]).await?;
+
+        let element = tab.find_element("h1").await?;
+        element.screenshot().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn emulate_media(&self, media_type: Option<&str>, features: &[(&str, &str)]) -> Result<&Self> {
+        let features: Vec<Value> = features
+            .iter()
+            .map(|(name, value)| json!({ "name": name, "value": value }))
+            .collect();
+
+        self.send_cmd("Emulation.setEmulatedMedia", json!({
+            "media": media_type.unwrap_or_default(),
+            "features": features
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Emulate `prefers-color-scheme: dark`, or `light` when `dark` is false.
+
+    Shorthand for [`emulate_media`] with only this feature, so it replaces other media
+    overrides. Pages render their own dark theme, unlike with [`set_auto_dark_mode`].
+
+    [`emulate_media`]: struct.Tab.html#method.emulate_media
+    [`set_auto_dark_mode`]: struct.Tab.html#method.set_auto_dark_mode
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_dark_mode(true).await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_dark_mode(&self, dark: bool) -> Result<&Self> {
+        let scheme = if dark { "dark" } else { "light" };
+
+        self.emulate_media(None, &[("prefers-color-scheme", scheme)]).await
+    }
+
+    /**
+    Force Chrome's auto dark mode on or off, or restore the browser default with `None`.
+
+    Auto dark mode darkens light sites that have no dark theme of their own. Unlike
+    emulating `prefers-color-scheme`, it affects sites that ignore the media query.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_auto_dark_mode(Some(true)).await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_auto_dark_mode(&self, enabled: Option<bool>) -> Result<&Self> {
+        let params = match enabled {
+            Some(enabled) => json!({ "enabled": enabled }),
+            None => json!({}),
+        };
+
+        self.send_cmd("Emulation.setAutoDarkModeOverride", params).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Make the page believe it has focus, or stop doing so.
+
+    Headless pages are treated as unfocused, so `:focus-within`, `autofocus` styling and
+    rendering gated on focus or visibility may not show in captures without this.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_page_focused(true).await?;
+        tab.set_content("<input autofocus>").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_page_focused(&self, focused: bool) -> Result<&Self> {
+        self.send_cmd("Emulation.setFocusEmulationEnabled", json!({
+            "enabled": focused
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Emulate a pinch-zoom state by setting the page scale factor; `1.0` resets it.
+
+    Unlike the device scale factor of a [`Viewport`], this zooms into the page like a
+    pinch gesture on a mobile device, so the layout viewport stays the same size.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, Viewport};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_viewport(&Viewport::new(390, 844).with_mobile(true)).await?;
+        tab.set_page_scale_factor(2.0).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_page_scale_factor(&self, factor: f64) -> Result<&Self> {
+        if !(factor > 0.0 && factor.is_finite()) {
+            return Err(anyhow::anyhow!("Page scale factor must be positive, got {}", factor));
+        }
+
+        self.send_cmd("Emulation.setPageScaleFactor", json!({
+            "pageScaleFactor": factor
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Clear all emulation overrides of this tab, e.g. before reusing a pooled tab.
+
+    Resets device metrics, touch, media, timezone, locale, geolocation, user agent,
+    idle state, scrollbars, the default background color, auto dark mode, page scale and
+    focus emulation.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, Viewport};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_viewport(&Viewport::new(390, 844).with_touch(true)).await?;
+        tab.reset_emulation().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn reset_emulation(&self) -> Result<&Self> {
+        let commands = [
+            ("Emulation.clearDeviceMetricsOverride", json!({})),
+            ("Emulation.setTouchEmulationEnabled", json!({ "enabled": false })),
+            ("Emulation.setEmulatedMedia", json!({ "media": "", "features": [] })),
+            ("Emulation.setTimezoneOverride", json!({ "timezoneId": "" })),
+            ("Emulation.setLocaleOverride", json!({})),
+            ("Emulation.clearGeolocationOverride", json!({})),
+            ("Emulation.setUserAgentOverride", json!({ "userAgent": "" })),
+            ("Emulation.clearIdleOverride", json!({})),
+            ("Emulation.setScrollbarsHidden", json!({ "hidden": false })),
+            ("Emulation.setDefaultBackgroundColorOverride", json!({})),
+            ("Emulation.setAutoDarkModeOverride", json!({})),
+            ("Emulation.setPageScaleFactor", json!({ "pageScaleFactor": 1.0 })),
+            ("Emulation.setFocusEmulationEnabled", json!({ "enabled": false })),
+        ];
+
+        for (method, params) in commands {
+            self.send_cmd(method, params).await?;
+        }
+
+        self.viewport.lock().unwrap().take();
+
+        Ok(self)
+    }
+
+    /**
+    Resize the viewport to exactly contain the current document.
+
+    Measures the CSS content size via `Page.getLayoutMetrics` and applies it through
+    `set_viewport`, so dynamically-sized content can be captured without surrounding
+    whitespace or scrollbars. Only the size changes: the device scale factor, mobile
+    emulation and screen size of the current viewport are kept.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div style='height: 2000px'>Tall card</div>").await?;
+        tab.fit_viewport_to_content().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn fit_viewport_to_content(&self) -> Result<&Self> {
+        let (width, height) = self.content_size().await?;
+        let current = self.viewport.lock().unwrap().clone();
+
+        let viewport = Viewport {
+            width: width.ceil() as u32,
+            height: height.ceil() as u32,
+            ..current.unwrap_or_else(|| Viewport::new(0, 0))
+        };
+
+        self.set_viewport(&viewport).await
+    }
+
+    /// The size of the document's content in CSS pixels, as `(width, height)`.
+    pub async fn content_size(&self) -> Result<(f64, f64)> {
+        let metrics = self.send_cmd("Page.getLayoutMetrics", json!({})).await?;
+
+        let size = metrics
+            .get("cssContentSize")
+            .context("Failed to get cssContentSize")?;
+        let width = size["width"].as_f64().context("Failed to get content width")?;
+        let height = size["height"].as_f64().context("Failed to get content height")?;
+
+        Ok((width, height))
+    }
+
+    /**
+    Capture the full page and crop away uniformly colored borders.
+
+    The color of the top-left pixel is taken as the background, and rows and columns
+    at the edges are removed while all their pixels are within `tolerance` (0-255 per
+    channel) of it, so antialiased near-background edges can be kept with a small value.
+    Any clip in `options` is ignored. WebP output is lossless.
+
+    Requires the `image` feature.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions, ImageFormat};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div style='margin: 200px auto; width: 300px'>Card</div>").await?;
+
+        let options = CaptureOptions::new().with_format(ImageFormat::Png);
+        let base64 = tab.screenshot_trimmed(options, 8).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn screenshot_trimmed(&self, options: CaptureOptions, tolerance: u8) -> Result<String> {
+        if cfg!(not(feature = "image")) {
+            bail!("Trimming captures requires the `image` feature");
+        }
+        let format = options.capture_format();
+
+        let png = self.screenshot_bytes(CaptureOptions {
+            format: ImageFormat::Png,
+            webp_lossless: false,
+            ..options.clone()
+        }.with_full_page(true)).await?;
+        let trimmed = image_utils::trim_uniform_border(&png, tolerance, format, options.quality())?;
+
+        Ok(general_utils::encode_base64(&trimmed))
+    }
+
+    /**
+    Capture the page as an MHTML archive.
+
+    Uses `Page.captureSnapshot` and returns the serialized page, including its resources.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Hello world!</h1>").await?;
+
+        let mhtml = tab.capture_mhtml().await?;
+        std::fs::write("page.mhtml", mhtml)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_mhtml(&self) -> Result<String> {
+        let res = self.send_cmd("Page.captureSnapshot", json!({
+            "format": "mhtml"
+        })).await?;
+
+        let data = res
+            .get("data")
+            .context("Failed to get data")?
+            .as_str()
+            .context("Failed to convert data to string")?
+            .to_string();
+
+        Ok(data)
+    }
+
+    /**
+    Print the page to PDF and return the document.
+
+    For large documents, [`print_to_pdf_stream`] avoids holding the whole PDF in memory.
+
+    [`print_to_pdf_stream`]: struct.Tab.html#method.print_to_pdf_stream
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, PdfOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Invoice #42</h1>").await?;
+
+        let pdf = tab.print_to_pdf(PdfOptions::new().with_margin(0.4)).await?;
+        std::fs::write("invoice.pdf", pdf)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn print_to_pdf(&self, options: PdfOptions) -> Result<Vec<u8>> {
+        let res = self.send_cmd_within("Page.printToPDF", options.to_params(), self.timeouts().navigation).await?;
+
+        let data = res["data"]
+            .as_str()
+            .context("Failed to get PDF data")?;
+
+        general_utils::decode_base64(data).context("The browser sent malformed base64 PDF data")
+    }
+
+    /**
+    Print the page to PDF and stream the document into `writer`.
+
+    The PDF is read from Chrome in chunks of 1 MiB, so even large documents are never
+    held in memory as a whole. Returns the number of bytes written.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, PdfOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<h1>Report</h1>").await?;
+
+        // Any `AsyncWrite` works, e.g. a file or an HTTP response body.
+        let mut pdf = Vec::new();
+        tab.print_to_pdf_stream(&mut pdf, PdfOptions::new().with_print_background(true)).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn print_to_pdf_stream<W>(&self, writer: &mut W, options: PdfOptions) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        const CHUNK_SIZE: usize = 1 << 20;
+
+        let mut params = options.to_params();
+        params["transferMode"] = json!("ReturnAsStream");
+
+        let res = self.send_cmd_within("Page.printToPDF", params, self.timeouts().navigation).await?;
+        let handle = res["stream"]
+            .as_str()
+            .context("Failed to get PDF stream handle")?
+            .to_string();
+
+        let mut written = 0;
+        let res = async {
+            loop {
+                let chunk = self.send_cmd("IO.read", json!({
+                    "handle": handle,
+                    "size": CHUNK_SIZE
+                })).await?;
+
+                let data = chunk["data"].as_str().unwrap_or_default();
+                let bytes = if chunk["base64Encoded"].as_bool().unwrap_or(false) {
+                    general_utils::decode_base64(data)?
+                } else {
+                    data.as_bytes().to_vec()
+                };
+
+                writer.write_all(&bytes).await.context("Failed to write PDF data")?;
+                written += bytes.len() as u64;
+
+                if chunk["eof"].as_bool().unwrap_or(true) {
+                    break;
+                }
+            }
+
+            writer.flush().await.context("Failed to write PDF data")
+        }.await;
+
+        self.send_cmd("IO.close", json!({ "handle": handle })).await?;
+        res?;
+
+        Ok(written)
+    }
+
+    /**
+    Add a script that is evaluated in every new document before any of its own scripts.
+
+    Returns the identifier of the script.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.add_init_script("Date.now = () => 0;").await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn add_init_script(&self, source: &str) -> Result<String> {
+        let res = self.send_cmd("Page.addScriptToEvaluateOnNewDocument", json!({
+            "source": source
+        })).await?;
+
+        let identifier = res["identifier"]
+            .as_str()
+            .context("Failed to get script identifier")?
+            .to_string();
+
+        Ok(identifier)
+    }
+
+    /**
+    Reload the page and wait for its load event.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        tab.reload().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn reload(&self) -> Result<&Self> {
+        self.enable_page().await?;
+
+        let load_event = self.transport.listen_event(&self.session_id, "Page.loadEventFired").await?;
+        self.send_cmd("Page.reload", json!({})).await?;
+        self.transport.wait_for_event(load_event, self.timeouts().navigation).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Add an init script and reload the page so it also applies to the current document.
+
+    Returns the identifier of the script.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        tab.evaluate_on_new_document_and_reload(
+            "document.addEventListener('DOMContentLoaded', () => document.querySelector('.banner')?.remove());"
+        ).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn evaluate_on_new_document_and_reload(&self, source: &str) -> Result<String> {
+        let identifier = self.add_init_script(source).await?;
+        self.reload().await?;
+
+        Ok(identifier)
+    }
+
+    /**
+    Capture the `outerHTML` and a screenshot of the first element matching `selector`.
+
+    Both come from the same element in the same DOM state, e.g. for documentation that
+    shows markup and its rendering side by side. Returns the HTML and the base64 image.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, CaptureOptions};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<button>Click me</button>").await?;
+
+        let (html, base64) = tab
+            .capture_element_outer_html_and_image("button", CaptureOptions::new())
+            .await?;
+        println!("{html}: {} bytes of base64", base64.len());
+        Ok(())
+    }
+    ```
+    */
+    pub async fn capture_element_outer_html_and_image(
+        &self,
+        selector: &str,
+        options: CaptureOptions,
+    ) -> Result<(String, String)> {
+        let element = self.find_element(selector).await?;
+        let html = element.outer_html().await?;
+        let base64 = element.screenshot_with_options(options).await?;
+
+        Ok((html, base64))
+    }
+
+    /**
+    Find an element by CSS selector.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        let element = tab.find_element("h1").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn find_element(&self, selector: &str) -> Result<Element> {
+        let node_id = match self.query_selector(selector).await? {
+            Some(node_id) => node_id,
+            None => return Err(anyhow::anyhow!("Element not found")),
+        };
+
+        Element::new(self, node_id).await
+    }
+
+    /**
+    Find the first element matching any of `selectors`, trying them in order.
+
+    Returns the selector that matched along with the element, and fails only if none
+    of them match. Useful when the same element has different markup across variants
+    of a page.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div class='price-v2'>$10</div>").await?;
+
+        let (selector, _element) = tab.find_element_any(&[".price", ".price-v2"]).await?;
+        assert_eq!(selector, ".price-v2");
+        Ok(())
+    }
+    ```
+    */
+    pub async fn find_element_any<'s>(&self, selectors: &[&'s str]) -> Result<(&'s str, Element<'_>)> {
+        for &selector in selectors {
+            if let Some(node_id) = self.query_selector(selector).await? {
+                return Ok((selector, Element::new(self, node_id).await?));
+            }
+        }
+
+        Err(anyhow::anyhow!("None of the selectors matched: {}", selectors.join(", ")))
+    }
+
+    /**
+    Find the only element matching a CSS selector.
+
+    Unlike [`find_element`], which silently picks the first match, this fails if the
+    selector matches more than one element and reports how many it matched.
+
+    [`find_element`]: struct.Tab.html#method.find_element
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.set_content("<div class='card'>A</div><div class='card'>B</div>").await?;
+
+        // Fails: `.card` matched 2 elements
+        assert!(tab.find_element_strict(".card").await.is_err());
+        Ok(())
+    }
+    ```
+    */
+    pub async fn find_element_strict(&self, selector: &str) -> Result<Element<'_>> {
+        let res = self.send_cmd("DOM.querySelectorAll", json!({
+            "nodeId": self.document_node_id().await?,
+            "selector": selector
+        })).await?;
+
+        let node_ids = res["nodeIds"]
+            .as_array()
+            .context("Failed to get nodeIds")?;
+
+        match node_ids.as_slice() {
+            [] => Err(anyhow::anyhow!("Element not found")),
+            [node_id] => Element::new(self, node_id.as_u64().context("Failed to get nodeId")?).await,
+            _ => Err(anyhow::anyhow!("Selector {} matched {} elements, expected 1", selector, node_ids.len())),
+        }
+    }
+
+    /// The node id of the document root.
+    async fn document_node_id(&self) -> Result<u64> {
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "DOM.getDocument",
+            "params": {}
+        }).to_string();
+
+        let res = general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, self.timeouts().command).await?;
+
+        let msg = general_utils::serde_msg(&res);
+        let node_id = msg["result"]["root"]["nodeId"]
+            .as_u64()
+            .unwrap();
+
+        Ok(node_id)
+    }
+
+    /// Query the document for `selector`, returning the node id of the first match.
+    async fn query_selector(&self, selector: &str) -> Result<Option<u64>> {
+        let node_id = self.document_node_id().await?;
+
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "DOM.querySelector",
+            "params": {
+                "nodeId": node_id,
+                "selector": selector
+            }
+        }).to_string();
+
+        let res = general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, self.timeouts().command).await?;
+
+        let msg = general_utils::serde_msg(&res);
+
+        // A node id of 0 means no element matched.
+        Ok(msg["result"]["nodeId"].as_u64().filter(|node_id| *node_id != 0))
+    }
+
+    /**
+    Wait until an element matches `selector` and return it.
+
+    Polls the document every 100ms and fails if no element matches after `timeout_ms`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        let element = tab.wait_for_selector("main", 10_000).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn wait_for_selector(&self, selector: &str, timeout_ms: u64) -> Result<Element<'_>> {
+        let deadline = time::Instant::now() + Duration::from_millis(timeout_ms);
+
+        loop {
+            if let Some(node_id) = self.query_selector(selector).await? {
+                return Element::new(self, node_id).await;
+            }
+            if time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Timeout while waiting for {}", selector));
+            }
+            time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    /**
+    Wait until no element matches `selector`, e.g. until a loading spinner is gone.
+
+    Polls the document every 100ms and fails if the element is still present after `timeout_ms`.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        tab.wait_for_selector_gone(".loading", 10_000).await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn wait_for_selector_gone(&self, selector: &str, timeout_ms: u64) -> Result<&Self> {
+        let deadline = time::Instant::now() + Duration::from_millis(timeout_ms);
+
+        while self.query_selector(selector).await?.is_some() {
+            if time::Instant::now() >= deadline {
+                return Err(anyhow::anyhow!("Timeout while waiting for {} to disappear", selector));
+            }
+            time::sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(self)
+    }
+
+    /**
+    Close the tab.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.close().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn activate(&self) -> Result<&Self> {
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "Target.activateTarget",
+            "params": {
+                "targetId": self.target_id
+            }
+        }).to_string();
+
+        general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, self.timeouts().command).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Bring the tab to the foreground with `Page.bringToFront`.
+
+    Unlike [`activate`], which goes through the browser target, this is sent to the
+    tab's own session, which more reliably makes a headful window composite the tab
+    before a capture.
+
+    [`activate`]: struct.Tab.html#method.activate
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new_with_head().await?;
+        let tab = browser.new_tab().await?;
+        tab.bring_to_front().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn bring_to_front(&self) -> Result<&Self> {
+        self.send_cmd("Page.bringToFront", json!({})).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Navigate to a URL.
+
+    # Warning
+
+    This API does not wait for the page to load, it is only used to navigate to local HTML files,
+    which is convenient for getting font and other resources.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+    use tokio::time;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto("https://www.rust-lang.org/").await?;
+        time::sleep(time::Duration::from_secs(5)).await;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn goto(&self, url: &str) -> Result<&Self> {
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "Page.navigate",
+            "params": {
+                "url": url
+            }
+        }).to_string();
+
+        general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, self.timeouts().command).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Navigate to a URL and wait for its load event.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto_and_wait("https://www.rust-lang.org/").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn goto_and_wait(&self, url: &str) -> Result<&Self> {
+        self.enable_page().await?;
+
+        let load_event = self.transport.listen_event(&self.session_id, "Page.loadEventFired").await?;
+        self.goto(url).await?;
+        self.transport.wait_for_event(load_event, self.timeouts().navigation).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Return the tab to a blank page, e.g. before rendering the next snippet in a reused tab.
+
+    Navigates to `about:blank` and waits for it to load. Emulation overrides are kept;
+    clear them with [`reset_emulation`].
+
+    [`reset_emulation`]: struct.Tab.html#method.reset_emulation
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        for html in ["<h1>One</h1>", "<h1>Two</h1>"] {
+            tab.set_content(html).await?;
+            tab.find_element("h1").await?.screenshot().await?;
+            tab.reset().await?;
+        }
+        Ok(())
+    }
+    ```
+    */
+    pub async fn reset(&self) -> Result<&Self> {
+        self.goto_and_wait("about:blank").await
+    }
+
+    /// Enable the `Page` domain for load events, once per tab.
+    async fn enable_page(&self) -> Result<()> {
+        if !self.page_enabled.load(Ordering::SeqCst) {
+            self.send_cmd("Page.enable", json!({})).await?;
+            self.page_enabled.store(true, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+
+    /// Enable the `Runtime` domain for console events, once per tab.
+    async fn enable_runtime(&self) -> Result<()> {
+        if !self.runtime_enabled.load(Ordering::SeqCst) {
+            self.send_cmd("Runtime.enable", json!({})).await?;
+            self.runtime_enabled.store(true, Ordering::SeqCst);
+        }
+
+        Ok(())
+    }
+
+    /**
+    Go back to the previous page in the tab's history and wait for its load event.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto_and_wait("https://www.rust-lang.org/").await?;
+        tab.goto_and_wait("https://www.rust-lang.org/learn").await?;
+        tab.history_back().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn history_back(&self) -> Result<&Self> {
+        self.navigate_history(-1).await
+    }
+
+    /// Go forward to the next page in the tab's history and wait for its load event.
+    pub async fn history_forward(&self) -> Result<&Self> {
+        self.navigate_history(1).await
+    }
+
+    async fn navigate_history(&self, offset: i64) -> Result<&Self> {
+        let history = self.send_cmd("Page.getNavigationHistory", json!({})).await?;
+
+        let current_index = history["currentIndex"]
+            .as_i64()
+            .context("Failed to get currentIndex")?;
+        let entry = history["entries"]
+            .as_array()
+            .and_then(|entries| entries.get(usize::try_from(current_index + offset).ok()?))
+            .context("No history entry to navigate to")?;
+
+        self.enable_page().await?;
+
+        let load_event = self.transport.listen_event(&self.session_id, "Page.loadEventFired").await?;
+        self.send_cmd("Page.navigateToHistoryEntry", json!({
+            "entryId": entry["id"]
+        })).await?;
+        self.transport.wait_for_event(load_event, self.timeouts().navigation).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Intercept every request of this tab and decide its fate with `handler`.
+
+    The handler receives the URL, method, headers and resource type of each request and
+    returns whether to continue it, fail it, fulfill it with a custom response, or continue
+    it with another URL. This makes it possible to stub out API responses and capture
+    deterministic states. Calling this again replaces the previous handler.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, InterceptAction};
+    use serde_json::json;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        tab.set_intercept_requests(|request| {
+            if request.url.ends_with("/api/data") {
+                InterceptAction::json(&json!({ "items": [1, 2, 3] }))
+            } else if request.resource_type == "Image" {
+                InterceptAction::Fail
+            } else {
+                InterceptAction::Continue
+            }
+        }).await?;
+
+        tab.goto_and_wait("https://example.com").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_intercept_requests<F>(&self, handler: F) -> Result<&Self>
+    where
+        F: Fn(&InterceptedRequest) -> InterceptAction + Send + Sync + 'static,
+    {
+        self.stop_interceptor();
+
+        // Paused requests queue up in the subscription until the task is spawned, so no
+        // task is left running if enabling fails.
+        let events = self.transport.subscribe(&self.session_id, "Fetch.requestPaused").await?;
+        self.send_cmd("Fetch.enable", json!({
+            "patterns": [{ "urlPattern": "*" }]
+        })).await?;
+
+        let task = tokio::spawn(intercept::run_interceptor(
+            Arc::downgrade(&self.transport),
+            self.session_id.clone(),
+            events,
+            Arc::new(handler),
+        ));
+        *self.interceptor.lock().unwrap() = Some(task);
+
+        Ok(self)
+    }
+
+    /// Stop intercepting requests and let them all through again.
+    pub async fn clear_intercept_requests(&self) -> Result<&Self> {
+        self.send_cmd("Fetch.disable", json!({})).await?;
+        self.stop_interceptor();
+
+        Ok(self)
+    }
+
+    fn stop_interceptor(&self) {
+        if let Some(task) = self.interceptor.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    /**
+    Send `headers` with every request of this tab, e.g. an `Authorization` header for
+    the API calls of the page being captured.
+
+    The headers apply to all later navigations and subresource requests of the tab and
+    persist for its lifetime. Each call replaces the previous headers; pass an empty map
+    to clear them.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+    use std::collections::HashMap;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        let headers = HashMap::from([("Authorization".to_string(), "Bearer token".to_string())]);
+        tab.set_extra_http_headers(headers).await?;
+        tab.goto_and_wait("https://example.com/dashboard").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_extra_http_headers(&self, headers: HashMap<String, String>) -> Result<&Self> {
+        self.send_cmd("Network.enable", json!({})).await?;
+        self.send_cmd("Network.setExtraHTTPHeaders", json!({
+            "headers": headers
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Set cookies in the browser, e.g. a session cookie before navigating to an
+    authenticated page.
+
+    The cookies are set through this tab's session and apply to every later request
+    matching their domain and path, including navigations of this tab.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::{Browser, Cookie};
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        let mut cookie = Cookie::new("session", "secret", "example.com");
+        cookie.secure = true;
+        cookie.http_only = true;
+        tab.set_cookies(&[cookie]).await?;
+        tab.goto_and_wait("https://example.com/account").await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn set_cookies(&self, cookies: &[Cookie]) -> Result<&Self> {
+        self.send_cmd("Network.enable", json!({})).await?;
+        self.send_cmd("Network.setCookies", json!({
+            "cookies": cookies
+        })).await?;
+
+        Ok(self)
+    }
+
+    /**
+    Get the cookies that would be sent with a request to the tab's current URL.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto_and_wait("https://example.com").await?;
+
+        for cookie in tab.get_cookies().await? {
+            println!("{}={}", cookie.name, cookie.value);
+        }
+        Ok(())
+    }
+    ```
+    */
+    pub async fn get_cookies(&self) -> Result<Vec<Cookie>> {
+        let res = self.send_cmd("Network.getCookies", json!({})).await?;
+
+        serde_json::from_value(res["cookies"].clone()).context("Failed to parse cookies")
+    }
+
+    /**
+    Get the cookies that would be sent with requests to any of `urls`.
+
+    Lets a session cookie be extracted after a login flow without fetching and
+    filtering every cookie of the browser.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.goto_and_wait("https://example.com/login").await?;
+
+        let cookies = tab.get_cookies_for_urls(&["https://example.com"]).await?;
+        let session = cookies.iter().find(|cookie| cookie.name == "session");
+        Ok(())
+    }
+    ```
+    */
+    pub async fn get_cookies_for_urls(&self, urls: &[&str]) -> Result<Vec<Cookie>> {
+        let res = self.send_cmd("Network.getCookies", json!({
+            "urls": urls
+        })).await?;
+
+        serde_json::from_value(res["cookies"].clone()).context("Failed to parse cookies")
+    }
+
+    /**
+    Start recording the network traffic of this tab, discarding any previous recording.
+
+    Requests, responses, redirects and failures are collected until
+    `stop_network_recording` is called or the tab is dropped, and can be exported with
+    `export_har` at any time.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+
+        tab.start_network_recording().await?;
+        tab.goto_and_wait("https://example.com").await?;
+        std::fs::write("example.har", tab.export_har()?)?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn start_network_recording(&self) -> Result<&Self> {
+        self.stop_recorder();
+        *self.recording.lock().unwrap() = Recording::default();
+
+        let mut receivers = Vec::with_capacity(network::RECORDED_EVENTS.len());
+        for method in network::RECORDED_EVENTS {
+            receivers.push(self.transport.subscribe(&self.session_id, method).await?);
+        }
+        let task = tokio::spawn(network::run_recorder(self.recording.clone(), receivers));
+        *self.recorder.lock().unwrap() = Some(task);
+
+        self.send_cmd("Network.enable", json!({})).await?;
+
+        Ok(self)
+    }
+
+    /// Stop recording network traffic; the recording so far is kept for `export_har`.
+    ///
+    /// The `Network` domain stays enabled, as cookies, extra HTTP headers and
+    /// `wait_for_network_idle` rely on it too.
+    pub async fn stop_network_recording(&self) -> Result<&Self> {
+        self.stop_recorder();
+
+        Ok(self)
+    }
+
+    /// The network traffic recorded with `start_network_recording` as HAR 1.2 JSON.
+    pub fn export_har(&self) -> Result<String> {
+        let har = self.recording.lock().unwrap().to_har();
+        serde_json::to_string_pretty(&har).context("Failed to serialize HAR")
+    }
+
+    fn stop_recorder(&self) {
+        if let Some(task) = self.recorder.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    /**
+    Close the tab.
+
+    # Example
+    ```no_run
+    use cdp_html_shot::Browser;
+    use anyhow::Result;
+
+    #[tokio::main]
+    async fn main() -> Result<()> {
+        let browser = Browser::new().await?;
+        let tab = browser.new_tab().await?;
+        tab.close().await?;
+        Ok(())
+    }
+    ```
+    */
+    pub async fn close(&self) -> Result<()> {
+        let msg_id = self.transport.next_id();
+        let msg = json!({
+            "id": msg_id,
+            "method": "Target.closeTarget",
+            "params": {
+                "targetId": self.target_id
+            }
+        }).to_string();
+
+        general_utils::send_and_get_msg_within(self.transport.clone(), msg_id, &self.session_id, msg, self.timeouts().command).await?;
+
+        Ok(())
+    }
+}
+
+impl Drop for Tab {
+    fn drop(&mut self) {
+        self.stop_interceptor();
+        self.stop_recorder();
+    }
+}
+
+/// Append `content` to `out` so that it can be embedded in a ``String.raw`...` `` template
+/// literal which has `BACKTICK` and `DOLLAR_BRACE` constants in scope.
+fn escape_template_literal(content: &str, out: &mut String) {
+    let mut rest = content;
+
+    while let Some(index) = rest.find(['`', '$']) {
+        out.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with('`') {
+            out.push_str("${BACKTICK}");
+            rest = &rest[1..];
+        } else if rest.starts_with("${") {
+            out.push_str("${DOLLAR_BRACE}");
+            rest = &rest[2..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+
+    out.push_str(rest);
+}
+
+/// Whether `clip` extends beyond `viewport`, both in page coordinates.
+fn exceeds_viewport(clip: &ClipRegion, viewport: &ClipRegion) -> bool {
+    clip.x < viewport.x
+        || clip.y < viewport.y
+        || clip.x + clip.width > viewport.x + viewport.width
+        || clip.y + clip.height > viewport.y + viewport.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_inside_viewport_does_not_exceed() {
+        let viewport = ClipRegion::new(0.0, 0.0, 800.0, 600.0);
+
+        assert!(!exceeds_viewport(&ClipRegion::new(0.0, 0.0, 800.0, 600.0), &viewport));
+        assert!(!exceeds_viewport(&ClipRegion::new(100.0, 100.0, 200.0, 200.0), &viewport));
+    }
+
+    #[test]
+    fn clip_partly_below_the_fold_exceeds() {
+        let viewport = ClipRegion::new(0.0, 0.0, 800.0, 600.0);
+
+        assert!(exceeds_viewport(&ClipRegion::new(0.0, 500.0, 800.0, 200.0), &viewport));
+    }
+
+    #[test]
+    fn clip_fully_below_the_fold_exceeds() {
+        let viewport = ClipRegion::new(0.0, 0.0, 800.0, 600.0);
+
+        assert!(exceeds_viewport(&ClipRegion::new(0.0, 1200.0, 800.0, 200.0), &viewport));
+    }
+
+    #[test]
+    fn clip_is_relative_to_the_scrolled_viewport() {
+        let scrolled = ClipRegion::new(0.0, 1000.0, 800.0, 600.0);
+
+        assert!(!exceeds_viewport(&ClipRegion::new(0.0, 1200.0, 800.0, 200.0), &scrolled));
+        assert!(exceeds_viewport(&ClipRegion::new(0.0, 0.0, 800.0, 200.0), &scrolled));
+    }
+
+    #[test]
+    fn escape_template_literal_replaces_backticks_and_substitutions() {
+        let mut out = String::new();
+        escape_template_literal("a`b${c}$d$", &mut out);
+
+        assert_eq!(out, "a${BACKTICK}b${DOLLAR_BRACE}c}$d$");
+    }
+
+    #[test]
+    fn escape_template_literal_keeps_plain_content() {
+        let mut out = String::from("prefix ");
+        escape_template_literal("<p>\\n 100 $ é</p>", &mut out);
+
+        assert_eq!(out, "prefix <p>\\n 100 $ é</p>");
+    }
+}
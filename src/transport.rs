@@ -5,13 +5,15 @@ use futures_util::StreamExt;
 use anyhow::{anyhow, Result};
 use tokio::sync::{mpsc, oneshot};
 use serde::{Deserialize, Serialize};
-use tokio_tungstenite::connect_async;
+use tokio_tungstenite::connect_async_with_config;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
 use std::{
     collections::HashMap,
     sync::{Arc, Condvar, Mutex},
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
-use crate::transport_actor::{TransportActor, TransportMessage, TransportResponse};
+use crate::transport_actor::{TargetMessage, TransportActor, TransportMessage, TransportResponse};
 
 #[derive(Debug)]
 pub(crate) struct ShutdownSignal {
@@ -44,40 +46,92 @@ impl ShutdownSignal {
 #[derive(Debug, Serialize, Deserialize)]
 pub(crate) struct Response {
     pub(crate) id: u64,
+    #[serde(default)]
     pub(crate) result: Value,
+    /// Set instead of `result` when the command failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<Value>,
+}
+
+/// Default limit for the size of a single incoming WebSocket message or frame.
+///
+/// Large enough for multi-megabyte full-page captures, which arrive base64-encoded
+/// and escaped once more inside `Target.receivedMessageFromTarget`.
+pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: usize = 256 << 20;
+
+/// Timeouts applied to the messages of a transport.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timeouts {
+    /// For the reply to a single command.
+    pub(crate) command: Duration,
+    /// For page loads, i.e. `set_content`, `reload` and waiting for navigation.
+    pub(crate) navigation: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            command: Duration::from_secs(10),
+            navigation: Duration::from_secs(60),
+        }
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct Transport {
     tx: mpsc::Sender<TransportMessage>,
-    shutdown_tx: Option<oneshot::Sender<()>>,
+    shutdown_tx: Mutex<Option<oneshot::Sender<bool>>>,
     shutdown_signal: Arc<ShutdownSignal>,
+    id_counter: Arc<AtomicUsize>,
+    pub(crate) timeouts: Timeouts,
 }
 
 unsafe impl Send for Transport {}
 unsafe impl Sync for Transport {}
 
 impl Transport {
-    pub(crate) async fn new(ws_url: &str) -> Result<Self> {
-        let (ws_stream, _) = connect_async(ws_url).await?;
+    pub(crate) async fn new(
+        ws_url: &str,
+        timeouts: Timeouts,
+        keepalive_interval: Option<Duration>,
+        max_message_size: usize,
+    ) -> Result<Self> {
+        let ws_config = WebSocketConfig {
+            max_message_size: Some(max_message_size),
+            max_frame_size: Some(max_message_size),
+            ..Default::default()
+        };
+
+        let (ws_stream, _) = connect_async_with_config(ws_url, Some(ws_config), false).await?;
         let (ws_sink, ws_stream) = ws_stream.split();
 
         let (tx, rx) = mpsc::channel::<TransportMessage>(100);
         let (shutdown_tx, shutdown_rx) = oneshot::channel();
         let signal = Arc::new(ShutdownSignal::new());
         let signal_clone = signal.clone();
+        let id_counter = Arc::new(AtomicUsize::new(0));
 
         let actor = TransportActor {
             pending_requests: HashMap::new(),
+            event_listeners: HashMap::new(),
+            event_subscribers: HashMap::new(),
             ws_sink,
             command_rx: rx,
             shutdown_rx,
             shutdown_signal: signal_clone,
+            id_counter: id_counter.clone(),
+            keepalive: keepalive_interval
+                .map(|period| time::interval_at(time::Instant::now() + period, period)),
         };
 
         tokio::spawn(actor.run(ws_stream));
 
-        Ok(Self { tx, shutdown_tx: Some(shutdown_tx), shutdown_signal: signal })
+        Ok(Self { tx, shutdown_tx: Mutex::new(Some(shutdown_tx)), shutdown_signal: signal, id_counter, timeouts })
+    }
+
+    /// Next message id, unique within this transport's connection.
+    pub(crate) fn next_id(&self) -> usize {
+        self.id_counter.fetch_add(1, Ordering::SeqCst) + 1
     }
 
     pub(crate) async fn send(&self, command: Value) -> Result<TransportResponse> {
@@ -85,30 +139,87 @@ impl Transport {
 
         self.tx.send(TransportMessage::Request(command, response_tx)).await?;
 
-        match time::timeout(Duration::from_secs(5), response_rx).await {
+        match time::timeout(self.timeouts.command, response_rx).await {
             Ok(response) => response?,
             Err(_) => Err(anyhow!("Timeout while waiting for response")),
         }
     }
 
-    pub(crate) async fn get_target_msg(&self, msg_id: usize) -> Result<TransportResponse> {
+    pub(crate) async fn get_target_msg(&self, msg_id: usize, timeout: Duration) -> Result<TransportResponse> {
         let (response_tx, response_rx) = oneshot::channel();
 
         self.tx.send(TransportMessage::ListenTargetMessage(msg_id as u64, response_tx)).await?;
 
-        match time::timeout(Duration::from_secs(5), response_rx).await {
+        match time::timeout(timeout, response_rx).await {
             Ok(response) => response?,
             Err(_) => Err(anyhow!("Timeout while waiting for response")),
         }
     }
 
-    pub(crate) fn shutdown(&mut self) {
-        self.shutdown_tx
-            .take()
-            .unwrap()
-            .send(())
-            .unwrap();
+    /// Register a listener for the next `method` event of a session.
+    ///
+    /// The listener is registered before this returns, so a command sent afterwards
+    /// cannot trigger the event before it is being listened for.
+    pub(crate) async fn listen_event(
+        &self,
+        session_id: &str,
+        method: &str,
+    ) -> Result<oneshot::Receiver<Result<TransportResponse>>> {
+        let (response_tx, response_rx) = oneshot::channel();
+
+        self.tx.send(TransportMessage::WaitForEvent(
+            session_id.to_string(),
+            method.to_string(),
+            response_tx,
+        )).await?;
 
-        self.shutdown_signal.wait();
+        Ok(response_rx)
+    }
+
+    /// Subscribe to every `method` event of a session until the receiver is dropped.
+    ///
+    /// An empty `session_id` subscribes to events of the browser itself, which are
+    /// received as is rather than wrapped in `Target.receivedMessageFromTarget`.
+    /// The receiver yields `None` once the connection is closed.
+    pub(crate) async fn subscribe(
+        &self,
+        session_id: &str,
+        method: &str,
+    ) -> Result<mpsc::UnboundedReceiver<TargetMessage>> {
+        let (event_tx, event_rx) = mpsc::unbounded_channel();
+
+        self.tx.send(TransportMessage::Subscribe(
+            session_id.to_string(),
+            method.to_string(),
+            event_tx,
+        )).await?;
+
+        Ok(event_rx)
+    }
+
+    /// Wait up to `timeout` for an event registered with `listen_event`.
+    pub(crate) async fn wait_for_event(
+        &self,
+        event_rx: oneshot::Receiver<Result<TransportResponse>>,
+        timeout: Duration,
+    ) -> Result<TransportResponse> {
+        match time::timeout(timeout, event_rx).await {
+            Ok(response) => response?,
+            Err(_) => Err(anyhow!("Timeout while waiting for event")),
+        }
+    }
+
+    /// Close the connection, first sending `Browser.close` if `close_browser` is set.
+    ///
+    /// Takes `&self` so a transport shared with tabs can be shut down; later calls, and
+    /// calls after the connection was lost, do nothing.
+    pub(crate) fn shutdown(&self, close_browser: bool) {
+        let Some(shutdown_tx) = self.shutdown_tx.lock().unwrap().take() else {
+            return;
+        };
+
+        if shutdown_tx.send(close_browser).is_ok() {
+            self.shutdown_signal.wait();
+        }
     }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use tokio::time;
 use tokio::net::TcpStream;
 use anyhow::{anyhow, Result};
 use serde_json::{json, Value};
@@ -8,6 +9,7 @@ use futures_util::stream::{SplitSink, SplitStream};
 use std::{
     sync::Arc,
     collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 use tokio_tungstenite::{
     MaybeTlsStream,
@@ -17,13 +19,14 @@ use tokio_tungstenite::{
 
 
 use crate::general_utils;
-use crate::general_utils::next_id;
 use crate::transport::{Response, ShutdownSignal};
 
 #[derive(Debug)]
 pub(crate) enum TransportMessage {
     Request(Value, oneshot::Sender<Result<TransportResponse>>),
     ListenTargetMessage(u64, oneshot::Sender<Result<TransportResponse>>),
+    WaitForEvent(String, String, oneshot::Sender<Result<TransportResponse>>),
+    Subscribe(String, String, mpsc::UnboundedSender<TargetMessage>),
 }
 
 #[derive(Debug)]
@@ -34,13 +37,17 @@ pub(crate) enum TransportResponse {
 
 pub(crate) struct TransportActor {
     pub(crate) pending_requests: HashMap<u64, oneshot::Sender<Result<TransportResponse>>>,
+    pub(crate) event_listeners: HashMap<(String, String), Vec<oneshot::Sender<Result<TransportResponse>>>>,
+    pub(crate) event_subscribers: HashMap<(String, String), Vec<mpsc::UnboundedSender<TargetMessage>>>,
     pub(crate) ws_sink: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
     pub(crate) command_rx: mpsc::Receiver<TransportMessage>,
-    pub(crate) shutdown_rx: oneshot::Receiver<()>,
+    pub(crate) shutdown_rx: oneshot::Receiver<bool>,
     pub(crate) shutdown_signal: Arc<ShutdownSignal>,
+    pub(crate) id_counter: Arc<AtomicUsize>,
+    pub(crate) keepalive: Option<time::Interval>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct TargetMessage {
     method: String,
     pub(crate) params: Value,
@@ -49,6 +56,8 @@ pub(crate) struct TargetMessage {
 impl TransportActor {
     pub(crate) async fn run(mut self, mut ws_stream: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>)
     {
+        let mut keepalive = self.keepalive.take();
+
         loop {
             tokio::select! {
                 Some(msg) = ws_stream.next() => {
@@ -73,22 +82,33 @@ impl TransportActor {
                     match msg {
                         TransportMessage::Request(cmd, response_tx) => self.handle_req(cmd, response_tx).await,
                         TransportMessage::ListenTargetMessage(msg_id, response_tx) => self.listen_target_msg(msg_id, response_tx).await,
+                        TransportMessage::WaitForEvent(session_id, method, response_tx) => self.listen_event(session_id, method, response_tx).await,
+                        TransportMessage::Subscribe(session_id, method, event_tx) => self.subscribe(session_id, method, event_tx).await,
                     };
                 }
 
-                _ = &mut self.shutdown_rx => {
-                    let command = json!({
-                            "id": next_id(),
-                            "method": "Browser.close",
-                            "params": {}
-                        });
+                _ = async { keepalive.as_mut().unwrap().tick().await }, if keepalive.is_some() => {
+                    if let Err(e) = self.ws_sink.send(Message::Ping(Vec::new())).await {
+                        self.handle_error(anyhow!("{e}")).await;
+                        break;
+                    }
+                }
 
-                    let msg = Message::Text(serde_json::to_string(&command).unwrap());
+                close_browser = &mut self.shutdown_rx => {
+                    if close_browser.unwrap_or(true) {
+                        let command = json!({
+                                "id": self.id_counter.fetch_add(1, Ordering::SeqCst) + 1,
+                                "method": "Browser.close",
+                                "params": {}
+                            });
 
-                    let  _ = self.ws_sink
-                        .send(msg)
-                        .await
-                        .is_ok();
+                        let msg = Message::Text(serde_json::to_string(&command).unwrap());
+
+                        let  _ = self.ws_sink
+                            .send(msg)
+                            .await
+                            .is_ok();
+                    }
 
                     let  _ = self.ws_sink
                         .close()
@@ -126,20 +146,58 @@ impl TransportActor {
 
     async fn handle_res(&mut self, response: Response) {
         if let Some(sender) = self.pending_requests.remove(&response.id) {
-            let _ = sender.send(Ok(TransportResponse::Response(response)));
+            let res = match &response.error {
+                Some(error) => Err(cdp_error(error)),
+                None => Ok(TransportResponse::Response(response)),
+            };
+            let _ = sender.send(res);
         }
     }
 
     async fn handle_target_msg(&mut self, msg: TargetMessage) {
         if &msg.method != "Target.receivedMessageFromTarget" {
+            self.handle_browser_event(msg).await;
             return;
         }
         let message = general_utils::serde_msg(&msg);
         if message.get("id").is_none() {
+            self.handle_event(msg, &message).await;
             return;
         }
         if let Some(sender) = self.pending_requests.remove(&message.get("id").unwrap().as_u64().unwrap()) {
-            let _ = sender.send(Ok(TransportResponse::Target(msg)));
+            let res = match message.get("error") {
+                Some(error) => Err(cdp_error(error)),
+                None => Ok(TransportResponse::Target(msg)),
+            };
+            let _ = sender.send(res);
+        }
+    }
+
+    async fn handle_event(&mut self, msg: TargetMessage, message: &Value) {
+        let (Some(session_id), Some(method)) = (msg.params["sessionId"].as_str(), message["method"].as_str()) else {
+            return;
+        };
+
+        self.dispatch_event((session_id.to_string(), method.to_string()), msg).await;
+    }
+
+    /// Dispatch an event of the browser itself, e.g. `Target.targetCreated`, to the
+    /// listeners of the empty session id. The message is passed on as is, not wrapped.
+    async fn handle_browser_event(&mut self, msg: TargetMessage) {
+        self.dispatch_event((String::new(), msg.method.clone()), msg).await;
+    }
+
+    async fn dispatch_event(&mut self, key: (String, String), msg: TargetMessage) {
+        if let Some(senders) = self.event_listeners.remove(&key) {
+            for sender in senders {
+                let _ = sender.send(Ok(TransportResponse::Target(msg.clone())));
+            }
+        }
+        if let Some(senders) = self.event_subscribers.get_mut(&key) {
+            senders.retain(|sender| sender.send(msg.clone()).is_ok());
+            if senders.is_empty() {
+                self.event_subscribers.remove(&key);
+            }
         }
     }
 
@@ -147,15 +205,52 @@ impl TransportActor {
         for (_, sender) in self.pending_requests.drain() {
             let _ = sender.send(Err(anyhow!("Connection error: {}", error)));
         }
+        for (_, senders) in self.event_listeners.drain() {
+            for sender in senders {
+                let _ = sender.send(Err(anyhow!("Connection error: {}", error)));
+            }
+        }
     }
 
     async fn cleanup(&mut self) {
+        self.event_subscribers.clear();
         for (_, sender) in self.pending_requests.drain() {
             let _ = sender.send(Err(anyhow!("Connection closed")));
         }
+        for (_, senders) in self.event_listeners.drain() {
+            for sender in senders {
+                let _ = sender.send(Err(anyhow!("Connection closed")));
+            }
+        }
     }
 
     async fn listen_target_msg(&mut self, msg_id: u64, response_tx: oneshot::Sender<Result<TransportResponse>>) {
         self.pending_requests.insert(msg_id, response_tx);
     }
-}
\ No newline at end of file
+
+    async fn subscribe(&mut self, session_id: String, method: String, event_tx: mpsc::UnboundedSender<TargetMessage>) {
+        self.event_subscribers
+            .entry((session_id, method))
+            .or_default()
+            .push(event_tx);
+    }
+
+    async fn listen_event(&mut self, session_id: String, method: String, response_tx: oneshot::Sender<Result<TransportResponse>>) {
+        let listeners = self.event_listeners
+            .entry((session_id, method))
+            .or_default();
+
+        // Drop listeners whose wait timed out, so reusing a tab doesn't accumulate them.
+        listeners.retain(|listener| !listener.is_closed());
+        listeners.push(response_tx);
+    }
+}
+
+/// The error for a command reply carrying a CDP `error` object.
+fn cdp_error(error: &Value) -> anyhow::Error {
+    anyhow!(
+        "CDP error {}: {}",
+        error["code"].as_i64().unwrap_or_default(),
+        error["message"].as_str().unwrap_or("unknown error"),
+    )
+}
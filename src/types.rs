@@ -0,0 +1,170 @@
+use serde_json::Value;
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// The `SameSite` attribute of a [`Cookie`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+/// A browser cookie, as used by the CDP `Network` domain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    /// Expiration as seconds since the UNIX epoch, or `None` for a session cookie.
+    #[serde(default, deserialize_with = "session_as_none", skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+    #[serde(default)]
+    pub http_only: bool,
+    #[serde(default)]
+    pub secure: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Create a session cookie for `domain` with the path `/` and no other attributes.
+    pub fn new(name: impl Into<String>, value: impl Into<String>, domain: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            domain: domain.into(),
+            path: "/".to_string(),
+            expires: None,
+            http_only: false,
+            secure: false,
+            same_site: None,
+        }
+    }
+}
+
+/// CDP reports session cookies with an expiration of `-1`.
+fn session_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    let expires = Option::<f64>::deserialize(deserializer)?;
+    Ok(expires.filter(|expires| *expires >= 0.0))
+}
+
+/// A console message or uncaught exception of a page, as yielded by `Tab::on_console`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleMessage {
+    /// The console method called, e.g. `"log"`, `"warning"` or `"error"`. Uncaught
+    /// exceptions have the level `"error"`.
+    pub level: String,
+    /// The arguments formatted as text and separated by spaces, or the exception's
+    /// description.
+    pub text: String,
+    /// The arguments as JSON values, or their descriptions for values that don't
+    /// serialize, e.g. DOM nodes.
+    pub args: Vec<Value>,
+}
+
+impl ConsoleMessage {
+    /// Parse a `Runtime.consoleAPICalled` or `Runtime.exceptionThrown` event.
+    pub(crate) fn from_event(method: &str, params: &Value) -> Option<Self> {
+        match method {
+            "Runtime.consoleAPICalled" => {
+                let args: Vec<Value> = params["args"]
+                    .as_array()
+                    .map(|args| args.iter().map(remote_object_value).collect())
+                    .unwrap_or_default();
+                let text = args
+                    .iter()
+                    .map(|arg| match arg {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                Some(Self {
+                    level: params["type"].as_str().unwrap_or("log").to_string(),
+                    text,
+                    args,
+                })
+            }
+            "Runtime.exceptionThrown" => {
+                let details = &params["exceptionDetails"];
+                let text = details["exception"]["description"]
+                    .as_str()
+                    .or(details["text"].as_str())
+                    .unwrap_or("Uncaught exception")
+                    .to_string();
+
+                Some(Self {
+                    level: "error".to_string(),
+                    text,
+                    args: Vec::new(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The value of a CDP `RemoteObject`, falling back to its description.
+fn remote_object_value(object: &Value) -> Value {
+    if let Some(value) = object.get("value") {
+        return value.clone();
+    }
+
+    object["unserializableValue"]
+        .as_str()
+        .or(object["description"].as_str())
+        .or(object["type"].as_str())
+        .map(|text| Value::String(text.to_string()))
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn console_message_formats_arguments() {
+        let params = json!({
+            "type": "warning",
+            "args": [
+                { "type": "string", "value": "missing data" },
+                { "type": "number", "value": 42 },
+                { "type": "number", "unserializableValue": "NaN" },
+                { "type": "object", "subtype": "node", "description": "div#app" },
+            ],
+        });
+        let message = ConsoleMessage::from_event("Runtime.consoleAPICalled", &params).unwrap();
+
+        assert_eq!(message.level, "warning");
+        assert_eq!(message.text, "missing data 42 NaN div#app");
+        assert_eq!(message.args, vec![json!("missing data"), json!(42), json!("NaN"), json!("div#app")]);
+    }
+
+    #[test]
+    fn console_message_describes_exceptions() {
+        let params = json!({
+            "exceptionDetails": {
+                "text": "Uncaught",
+                "exception": { "description": "TypeError: x is undefined" },
+            },
+        });
+        let message = ConsoleMessage::from_event("Runtime.exceptionThrown", &params).unwrap();
+
+        assert_eq!(message.level, "error");
+        assert_eq!(message.text, "TypeError: x is undefined");
+        assert!(message.args.is_empty());
+
+        let params = json!({ "exceptionDetails": { "text": "Uncaught" } });
+        let message = ConsoleMessage::from_event("Runtime.exceptionThrown", &params).unwrap();
+        assert_eq!(message.text, "Uncaught");
+    }
+
+    #[test]
+    fn console_message_ignores_other_events() {
+        assert!(ConsoleMessage::from_event("Runtime.executionContextCreated", &json!({})).is_none());
+    }
+}
@@ -0,0 +1,131 @@
+/// Device metrics to emulate in a tab.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Viewport {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) device_scale_factor: f64,
+    pub(crate) is_mobile: bool,
+    pub(crate) has_touch: bool,
+    pub(crate) is_landscape: bool,
+    pub(crate) screen_width: Option<u32>,
+    pub(crate) screen_height: Option<u32>,
+    pub(crate) user_agent: Option<String>,
+}
+
+impl Viewport {
+    /// Create a desktop viewport of the given size in CSS pixels.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            device_scale_factor: 1.0,
+            is_mobile: false,
+            has_touch: false,
+            is_landscape: false,
+            screen_width: None,
+            screen_height: None,
+            user_agent: None,
+        }
+    }
+
+    /// Emulate an iPhone 14: 390x844 at 3x, with touch and Mobile Safari's user agent.
+    pub fn iphone_14() -> Self {
+        Self::mobile_device(390, 844, 3.0, "Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X) \
+            AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1")
+    }
+
+    /// Emulate a Pixel 7: 412x915 at 2.625x, with touch and Chrome for Android's user agent.
+    pub fn pixel_7() -> Self {
+        Self::mobile_device(412, 915, 2.625, "Mozilla/5.0 (Linux; Android 13; Pixel 7) \
+            AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36")
+    }
+
+    /// Emulate an iPad (9th generation): 810x1080 at 2x, with touch and Safari's user agent.
+    pub fn ipad() -> Self {
+        Self::mobile_device(810, 1080, 2.0, "Mozilla/5.0 (iPad; CPU OS 16_0 like Mac OS X) \
+            AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1")
+    }
+
+    fn mobile_device(width: u32, height: u32, device_scale_factor: f64, user_agent: &str) -> Self {
+        Self::portrait(width, height)
+            .with_device_scale_factor(device_scale_factor)
+            .with_mobile(true)
+            .with_touch(true)
+            .with_user_agent(user_agent)
+    }
+
+    /// Create a viewport in portrait orientation, with the shorter side as the width.
+    ///
+    /// The dimensions may be given in either order.
+    pub fn portrait(width: u32, height: u32) -> Self {
+        Self::new(width.min(height), width.max(height))
+    }
+
+    /// Create a viewport in landscape orientation, with the longer side as the width.
+    ///
+    /// The dimensions may be given in either order.
+    pub fn landscape(width: u32, height: u32) -> Self {
+        Self {
+            is_landscape: true,
+            ..Self::new(width.max(height), width.min(height))
+        }
+    }
+
+    /// Whether the orientation flag disagrees with the dimensions.
+    pub(crate) fn orientation_mismatch(&self) -> bool {
+        if self.is_landscape {
+            self.width < self.height
+        } else {
+            self.width > self.height && self.is_mobile
+        }
+    }
+
+    /// Whether both viewports emulate the same device metrics, ignoring touch emulation
+    /// and the user agent.
+    pub(crate) fn same_metrics(&self, other: &Viewport) -> bool {
+        Viewport { has_touch: other.has_touch, user_agent: other.user_agent.clone(), ..self.clone() } == *other
+    }
+
+    /// Set the device pixel ratio.
+    pub fn with_device_scale_factor(mut self, factor: f64) -> Self {
+        self.device_scale_factor = factor;
+        self
+    }
+
+    /// Set whether to emulate a mobile device (meta viewport, overlay scrollbars).
+    pub fn with_mobile(mut self, is_mobile: bool) -> Self {
+        self.is_mobile = is_mobile;
+        self
+    }
+
+    /// Set whether to emulate touch input.
+    pub fn with_touch(mut self, has_touch: bool) -> Self {
+        self.has_touch = has_touch;
+        self
+    }
+
+    /// Set whether the screen is in landscape orientation.
+    ///
+    /// The dimensions are left as is; prefer [`Viewport::portrait`] or
+    /// [`Viewport::landscape`], which also order them to match.
+    pub fn with_landscape(mut self, is_landscape: bool) -> Self {
+        self.is_landscape = is_landscape;
+        self
+    }
+
+    /// Set the size of the emulated screen, as read by `window.screen.width/height`.
+    ///
+    /// Defaults to the viewport size.
+    pub fn with_screen_size(mut self, width: u32, height: u32) -> Self {
+        self.screen_width = Some(width);
+        self.screen_height = Some(height);
+        self
+    }
+
+    /// Set the user agent to send and report as `navigator.userAgent` while this viewport
+    /// is in effect. The browser's own user agent is used by default.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+}
@@ -0,0 +1,85 @@
+/// Where a [`Watermark`] is placed on a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatermarkPosition {
+    /// The top left corner, inset by the margin.
+    TopLeft,
+    /// The top right corner, inset by the margin.
+    TopRight,
+    /// The bottom left corner, inset by the margin.
+    BottomLeft,
+    /// The bottom right corner, inset by the margin. The default.
+    #[default]
+    BottomRight,
+    /// The middle of the capture; the margin is ignored.
+    Center,
+}
+
+/**
+An image composited onto captures after they are taken, e.g. a logo or attribution.
+
+The image may be any format the `image` crate decodes, and its alpha channel is kept.
+Text watermarks are created with [`Browser::text_watermark`], which renders the text
+with the browser, as the `image` crate can't rasterize fonts.
+
+[`Browser::text_watermark`]: crate::Browser::text_watermark
+
+Requires the `image` feature; without it, captures with a watermark fail with an error.
+
+# Example
+```no_run
+use cdp_html_shot::{Browser, CaptureOptions, Watermark, WatermarkPosition};
+use anyhow::Result;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let browser = Browser::new().await?;
+    let watermark = Watermark::new(std::fs::read("logo.png")?)
+        .with_position(WatermarkPosition::BottomRight)
+        .with_margin(16)
+        .with_opacity(0.5);
+
+    let options = CaptureOptions::new().with_watermark(watermark);
+    let base64 = browser.capture_html_with_options("<h1>Hello world!</h1>", "h1", options).await?;
+    Ok(())
+}
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub(crate) image: Vec<u8>,
+    pub(crate) position: WatermarkPosition,
+    pub(crate) margin: u32,
+    pub(crate) opacity: f32,
+}
+
+impl Watermark {
+    /// Create a watermark from encoded image data, placed in the bottom right corner
+    /// with a margin of 8 pixels and full opacity.
+    pub fn new(image: impl Into<Vec<u8>>) -> Self {
+        Self {
+            image: image.into(),
+            position: WatermarkPosition::default(),
+            margin: 8,
+            opacity: 1.0,
+        }
+    }
+
+    /// Set where the watermark is placed.
+    pub fn with_position(mut self, position: WatermarkPosition) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// Set the distance in image pixels between the watermark and the capture's edges.
+    /// Ignored for [`WatermarkPosition::Center`].
+    pub fn with_margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Set the opacity of the watermark, from 0.0 (invisible) to 1.0 (opaque).
+    pub fn with_opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity.clamp(0.0, 1.0);
+        self
+    }
+}
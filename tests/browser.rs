@@ -0,0 +1,160 @@
+//! Tests that drive a real Chrome. They are ignored by default; run them with
+//! `cargo test --all-features -- --ignored` on a machine with Chrome installed.
+
+use anyhow::Result;
+use cdp_html_shot::{Browser, CaptureOptions, ImageFormat, Viewport};
+
+#[cfg(feature = "image")]
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn transparent_element_background_clears_ancestor_backgrounds() -> Result<()> {
+    use cdp_html_shot::ElementBackground;
+
+    let browser = Browser::new().await?;
+    let tab = browser.new_tab().await?;
+    tab.set_content("<body style='background: red'><div id='box' style='width: 100px; height: 100px'></div></body>").await?;
+    let element = tab.find_element("#box").await?;
+
+    let page = element.screenshot_bytes(CaptureOptions::new().with_raw_png(true)).await?;
+    let page = image::load_from_memory(&page)?.to_rgba8();
+    assert_eq!(page.get_pixel(0, 0).0, [255, 0, 0, 255]);
+
+    let options = CaptureOptions::new().with_element_background(ElementBackground::Transparent);
+    let transparent = element.screenshot_bytes(options).await?;
+    let transparent = image::load_from_memory(&transparent)?.to_rgba8();
+    assert_eq!(transparent.get_pixel(0, 0).0[3], 0);
+
+    // The ancestors' backgrounds are restored after the capture.
+    let background = tab.evaluate("getComputedStyle(document.body).backgroundColor").await?;
+    assert_eq!(background, "rgb(255, 0, 0)");
+
+    Ok(())
+}
+
+/// The pixel size of encoded PNG data, read from its `IHDR` chunk.
+fn png_size(png: &[u8]) -> (u32, u32) {
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    (width, height)
+}
+
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn full_page_screenshot_spans_the_document() -> Result<()> {
+    let browser = Browser::new().await?;
+    let tab = browser.new_tab().await?;
+    tab.set_viewport(&Viewport::new(800, 600)).await?;
+    tab.set_content("<body style='margin: 0'><div style='height: 3000px; background: teal'></div></body>").await?;
+
+    let options = CaptureOptions::new().with_format(ImageFormat::Png).with_full_page(true);
+    let png = tab.screenshot_bytes(options).await?;
+    assert_eq!(png_size(&png), (800, 3000));
+
+    // The viewport is restored after the capture.
+    assert_eq!(tab.evaluate("innerHeight").await?, 600);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn print_media_shows_print_only_elements() -> Result<()> {
+    let browser = Browser::new().await?;
+    let tab = browser.new_tab().await?;
+    tab.set_content("<style>#note { display: none } @media print { #note { display: block } }</style><p id='note'>Printed</p>").await?;
+
+    let visible = "getComputedStyle(document.getElementById('note')).display !== 'none'";
+    assert_eq!(tab.evaluate(visible).await?, false);
+
+    tab.emulate_media(Some("print"), &[]).await?;
+    assert_eq!(tab.evaluate(visible).await?, true);
+    tab.find_element("#note").await?.screenshot_bytes(CaptureOptions::new()).await?;
+
+    tab.emulate_media(None, &[]).await?;
+    assert_eq!(tab.evaluate(visible).await?, false);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn large_capture_exceeds_default_websocket_limits() -> Result<()> {
+    let browser = Browser::new().await?;
+    let tab = browser.new_tab().await?;
+    // Random pixels don't compress, so the PNG is far beyond tungstenite's 16 MiB frame limit.
+    tab.set_content(r#"
+        <body style="margin: 0">
+            <canvas id="noise" width="3000" height="3000" style="display: block"></canvas>
+            <script>
+                const context = document.getElementById("noise").getContext("2d");
+                const image = context.createImageData(3000, 3000);
+                for (let i = 0; i < image.data.length; i++) {
+                    image.data[i] = i % 4 === 3 ? 255 : Math.random() * 256;
+                }
+                context.putImageData(image, 0, 0);
+            </script>
+        </body>
+    "#).await?;
+
+    let options = CaptureOptions::new().with_format(ImageFormat::Png).with_full_page(true);
+    let png = tab.screenshot_bytes(options).await?;
+    assert!(png.len() > 16 << 20, "capture is only {} bytes", png.len());
+    assert_eq!(png_size(&png), (3000, 3000));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn concurrent_browsers_route_their_own_responses() -> Result<()> {
+    let (first, second) = tokio::try_join!(Browser::new(), Browser::new())?;
+
+    // Each transport numbers its commands from the same starting id, so the replies
+    // are only told apart by the connection they arrive on.
+    let capture = |browser: Browser, text: &'static str| async move {
+        let tab = browser.new_tab().await?;
+        tab.set_content(format!("<h1>{text}</h1>")).await?;
+        for _ in 0..20 {
+            assert_eq!(tab.evaluate("document.querySelector('h1').textContent").await?, text);
+        }
+        anyhow::Ok(())
+    };
+    tokio::try_join!(capture(first, "first"), capture(second, "second"))?;
+
+    Ok(())
+}
+
+#[cfg(feature = "image")]
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn text_watermark_is_composited_onto_captures() -> Result<()> {
+    let browser = Browser::new().await?;
+    let watermark = browser.text_watermark("<© Example>", "font: 20px sans-serif; color: black").await?;
+
+    let tab = browser.new_tab().await?;
+    tab.set_content("<body style='margin: 0'><div style='width: 400px; height: 200px'></div></body>").await?;
+    let element = tab.find_element("div").await?;
+
+    let options = CaptureOptions::new().with_format(ImageFormat::Png);
+    let plain = element.screenshot_bytes(options.clone()).await?;
+    let marked = element.screenshot_bytes(options.with_watermark(watermark)).await?;
+    assert_eq!(png_size(&marked), png_size(&plain));
+    assert_ne!(marked, plain);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[ignore = "requires Chrome"]
+async fn fit_viewport_to_content_keeps_device_metrics() -> Result<()> {
+    let browser = Browser::new().await?;
+    let tab = browser.new_tab().await?;
+    tab.set_viewport(&Viewport::iphone_14()).await?;
+    tab.set_content("<body style='margin: 0'><div style='width: 300px; height: 1500px'></div></body>").await?;
+
+    tab.fit_viewport_to_content().await?;
+    assert_eq!(tab.evaluate("devicePixelRatio").await?, 3);
+    assert_eq!(tab.evaluate("innerHeight").await?, 1500);
+
+    Ok(())
+}